@@ -0,0 +1,51 @@
+use cairo::word::{CairoWord, Decomposition};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use mina_curves::pasta::fp::Fp as F;
+
+/// A large program made of the instructions of the `test_cairo_output` runner test
+fn program(len: usize) -> Vec<CairoWord<F>> {
+    let instrs = [
+        0x400380007ffc7ffdu64,
+        0x482680017ffc8000,
+        0x208b7fff7fff7ffe,
+        0x480680017fff8000,
+        0x48307fff7fff8000,
+        0x48507fff7fff8000,
+        0x48307ffd7fff8000,
+        0x480a7ffd7fff8000,
+        0x48127ffb7fff8000,
+        0x1104800180018000,
+    ];
+    instrs
+        .iter()
+        .cycle()
+        .take(len)
+        .map(|i| CairoWord::new(F::from(*i)))
+        .collect()
+}
+
+pub fn bench_flags(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Flags decoding");
+    let prog = program(1 << 12);
+
+    // allocates a vector per instruction
+    group.bench_function("flags (2^12 instructions)", |b| {
+        b.iter(|| {
+            for word in prog.iter() {
+                black_box(word.flags());
+            }
+        })
+    });
+
+    // decodes into a stack array
+    group.bench_function("flags_array (2^12 instructions)", |b| {
+        b.iter(|| {
+            for word in prog.iter() {
+                black_box(word.flags_array());
+            }
+        })
+    });
+}
+
+criterion_group!(benches, bench_flags);
+criterion_main!(benches);
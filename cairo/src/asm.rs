@@ -0,0 +1,143 @@
+//! This module contains the building blocks of a small Cairo assembler.
+//! Operands are written in their textual form, such as `[ap + 3]`, `[fp - 1]` or `imm(42)`,
+//! and parsed into a structured [Operand] from which the register and offset can be read.
+
+use ark_ff::Field;
+use thiserror::Error;
+
+/// Errors that can arise when parsing assembly
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AsmError {
+    /// The operand is neither a memory reference nor an immediate
+    #[error("invalid operand {0}")]
+    InvalidOperand(String),
+    /// The memory reference uses a register other than `ap` or `fp`
+    #[error("invalid register {0}")]
+    InvalidRegister(String),
+    /// The offset is not a decimal number that fits in 16 bits
+    #[error("invalid offset {0}")]
+    InvalidOffset(String),
+    /// The immediate is not a decimal number
+    #[error("invalid immediate {0}")]
+    InvalidImmediate(String),
+}
+
+/// An operand of a Cairo assembly instruction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operand<F> {
+    /// Memory cell relative to the allocation pointer, as in `[ap + off]`
+    ApRel(i16),
+    /// Memory cell relative to the frame pointer, as in `[fp + off]`
+    FpRel(i16),
+    /// Immediate value, as in `imm(val)`
+    Imm(F),
+}
+
+impl<F: Field> Operand<F> {
+    /// Parses an operand from its textual form `[ap + off]`, `[fp - off]` or `imm(val)`
+    pub fn parse(s: &str) -> Result<Operand<F>, AsmError> {
+        let s = s.trim();
+        if let Some(val) = s.strip_prefix("imm(").and_then(|s| s.strip_suffix(')')) {
+            return parse_imm(val.trim()).map(Operand::Imm);
+        }
+        let inner = s
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .ok_or_else(|| AsmError::InvalidOperand(s.to_string()))?;
+        // spaces are optional, so `[ap+3]` is the same as `[ap + 3]`
+        let inner: String = inner.chars().filter(|c| !c.is_whitespace()).collect();
+        let (reg, rest) = match (inner.get(..2), inner.get(2..)) {
+            (Some(reg), Some(rest)) => (reg, rest),
+            _ => return Err(AsmError::InvalidOperand(s.to_string())),
+        };
+        let off = parse_off(rest)?;
+        match reg {
+            "ap" => Ok(Operand::ApRel(off)),
+            "fp" => Ok(Operand::FpRel(off)),
+            _ => Err(AsmError::InvalidRegister(reg.to_string())),
+        }
+    }
+}
+
+/// Parses the signed displacement following the register, which can be empty as in `[ap]`
+fn parse_off(s: &str) -> Result<i16, AsmError> {
+    if s.is_empty() {
+        return Ok(0);
+    }
+    let (neg, digits) = match (s.strip_prefix('+'), s.strip_prefix('-')) {
+        (Some(digits), _) => (false, digits),
+        (_, Some(digits)) => (true, digits),
+        _ => return Err(AsmError::InvalidOffset(s.to_string())),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(AsmError::InvalidOffset(s.to_string()));
+    }
+    // parse with the sign so that -2^15 is accepted
+    let off = if neg {
+        format!("-{}", digits).parse::<i16>()
+    } else {
+        digits.parse::<i16>()
+    };
+    off.map_err(|_| AsmError::InvalidOffset(s.to_string()))
+}
+
+/// Parses a decimal immediate, possibly negative, into a field element
+fn parse_imm<F: Field>(s: &str) -> Result<F, AsmError> {
+    let val = s
+        .parse::<i128>()
+        .map_err(|_| AsmError::InvalidImmediate(s.to_string()))?;
+    let abs = F::from(val.unsigned_abs());
+    if val < 0 {
+        Ok(-abs)
+    } else {
+        Ok(abs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::One;
+    use mina_curves::pasta::fp::Fp as F;
+
+    #[test]
+    fn test_parse_operand() {
+        assert_eq!(Operand::<F>::parse("[ap + 3]"), Ok(Operand::ApRel(3)));
+        assert_eq!(Operand::<F>::parse("[fp - 1]"), Ok(Operand::FpRel(-1)));
+        assert_eq!(Operand::<F>::parse("[ap]"), Ok(Operand::ApRel(0)));
+        assert_eq!(Operand::<F>::parse("[fp+0]"), Ok(Operand::FpRel(0)));
+        assert_eq!(
+            Operand::<F>::parse("[ap - 32768]"),
+            Ok(Operand::ApRel(i16::MIN))
+        );
+        assert_eq!(
+            Operand::<F>::parse("imm(42)"),
+            Ok(Operand::Imm(F::from(42u32)))
+        );
+        assert_eq!(Operand::<F>::parse("imm(-1)"), Ok(Operand::Imm(-F::one())));
+    }
+
+    #[test]
+    fn test_parse_operand_errors() {
+        assert_eq!(
+            Operand::<F>::parse("[pc + 1]"),
+            Err(AsmError::InvalidRegister("pc".to_string()))
+        );
+        assert_eq!(
+            Operand::<F>::parse("[ap + 32768]"),
+            Err(AsmError::InvalidOffset("+32768".to_string()))
+        );
+        assert_eq!(
+            Operand::<F>::parse("[fp * 2]"),
+            Err(AsmError::InvalidOffset("*2".to_string()))
+        );
+        assert_eq!(
+            Operand::<F>::parse("imm(x)"),
+            Err(AsmError::InvalidImmediate("x".to_string()))
+        );
+        assert_eq!(
+            Operand::<F>::parse("ap + 1"),
+            Err(AsmError::InvalidOperand("ap + 1".to_string()))
+        );
+    }
+}
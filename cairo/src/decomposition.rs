@@ -0,0 +1,309 @@
+//! This module contains the decomposition of a Cairo word into its offsets, bit-flags and flagsets.
+//! It only relies on `core` and `alloc`, so that instructions can be decoded without the standard library
+//! by disabling the default `std` feature, in which case the flags are read with [Decomposition::flags_array].
+
+use crate::flags::*;
+use ark_ff::{Field, ToBytes};
+use ark_std::vec::Vec;
+
+/// A Cairo word for the runner. Some words are instructions (which fit inside a `u64`). Others are immediate values (any `F` element).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CairoWord<F>(pub(crate) F);
+
+impl<F: Field> CairoWord<F> {
+    /// Creates a [CairoWord] from a field element
+    pub fn new(word: F) -> CairoWord<F> {
+        CairoWord(word)
+    }
+
+    /// Returns the content of the word as a field element
+    pub fn word(&self) -> F {
+        self.0
+    }
+
+    /// Returns the seven flagsets of the word
+    pub fn flagsets(&self) -> Flagsets {
+        Flagsets {
+            dst_reg: self.dst_reg(),
+            op0_reg: self.op0_reg(),
+            op1_src: self.op1_src(),
+            res_log: self.res_log(),
+            pc_up: self.pc_up(),
+            ap_up: self.ap_up(),
+            opcode: self.opcode(),
+        }
+    }
+}
+
+/// The flagsets of an instruction, as returned one by one by [Decomposition]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Flagsets {
+    /// Flagset for destination register
+    pub dst_reg: u8,
+    /// Flagset for first operand register
+    pub op0_reg: u8,
+    /// Flagset for second operand register
+    pub op1_src: u8,
+    /// Flagset for result logics
+    pub res_log: u8,
+    /// Flagset for program counter update
+    pub pc_up: u8,
+    /// Flagset for allocation pointer update
+    pub ap_up: u8,
+    /// Flagset for operation code
+    pub opcode: u8,
+}
+
+impl Flagsets {
+    /// Packs the flagsets back into the 16 bit-flags, as returned by [Decomposition::flag_bitmask]
+    pub fn pack(&self) -> u16 {
+        u16::from(self.dst_reg)
+            | u16::from(self.op0_reg) << 1
+            | u16::from(self.op1_src) << 2
+            | u16::from(self.res_log) << 5
+            | u16::from(self.pc_up) << 7
+            | u16::from(self.ap_up) << 10
+            | u16::from(self.opcode) << 12
+    }
+}
+
+/// Returns an offset of 16 bits to its biased representation in the interval `[-2^15,2^15)` as a field element
+pub(crate) fn bias<F: Field>(offset: F) -> F {
+    offset - F::from(2u16.pow(15u32)) // -2^15 + sum_(i=0..15) b_i * 2^i
+}
+
+/// Returns the first 64 bits of a field element, where bits beyond the size of the field are zero,
+/// as `CairoFieldHelpers::to_u64` does
+fn low_u64<F: Field>(word: F) -> u64 {
+    let mut bytes = Vec::new();
+    word.write(&mut bytes).expect("Failed to serialize field");
+    bytes
+        .iter()
+        .take(8)
+        .rev()
+        .fold(0, |acc, byte| acc << 8 | u64::from(*byte))
+}
+
+/// Returns the `pos`-th 16-bit chunk of a field element as another field element
+fn chunk_u16<F: Field>(word: F, pos: usize) -> F {
+    F::from((low_u64(word) >> (16 * pos)) as u16)
+}
+
+/// This trait contains methods that decompose a field element into [CairoWord] components.
+/// Implementors only read the offsets and the bit-flags, from which the other components are derived.
+pub trait Decomposition<F: Field> {
+    /// Returns the destination offset in biased representation
+    fn off_dst(&self) -> F;
+
+    /// Returns the first operand offset in biased representation
+    fn off_op0(&self) -> F;
+
+    /// Returns the second operand offset in biased representation
+    fn off_op1(&self) -> F;
+
+    /// Returns vector of 16 flags. Without the `std` feature, use [Decomposition::flags_array] instead.
+    #[cfg(feature = "std")]
+    fn flags(&self) -> Vec<F> {
+        self.flags_array().to_vec()
+    }
+
+    /// Returns array of 16 flags, without allocating a vector
+    fn flags_array(&self) -> [F; NUM_FLAGS] {
+        let mask = self.flag_bitmask();
+        let mut flags = [F::zero(); NUM_FLAGS];
+        for (i, flag) in flags.iter_mut().enumerate() {
+            *flag = F::from((mask >> i) & 1);
+        }
+        flags
+    }
+
+    /// Returns the 16 bit-flags as an integer whose i-th bit is the i-th bit-flag. This is the
+    /// canonical form of the flags, from which every bit-flag and flagset is derived.
+    fn flag_bitmask(&self) -> u16;
+
+    /// Returns i-th bit-flag, which is zero past the 16 bit-flags
+    fn flag_at(&self, pos: usize) -> F {
+        let bit = u32::try_from(pos)
+            .ok()
+            .and_then(|pos| self.flag_bitmask().checked_shr(pos))
+            .unwrap_or(0);
+        F::from(bit & 1)
+    }
+
+    /// Returns bit-flag for destination register as `F`
+    fn f_dst_fp(&self) -> F {
+        self.flag_at(0)
+    }
+
+    /// Returns bit-flag for first operand register as `F`
+    fn f_op0_fp(&self) -> F {
+        self.flag_at(1)
+    }
+
+    /// Returns bit-flag for immediate value for second register as `F`
+    fn f_op1_val(&self) -> F {
+        self.flag_at(2)
+    }
+
+    /// Returns bit-flag for frame pointer for second register as `F`
+    fn f_op1_fp(&self) -> F {
+        self.flag_at(3)
+    }
+
+    /// Returns bit-flag for allocation pointer for second regsiter as `F`
+    fn f_op1_ap(&self) -> F {
+        self.flag_at(4)
+    }
+
+    /// Returns bit-flag for addition operation in right side as `F`
+    fn f_res_add(&self) -> F {
+        self.flag_at(5)
+    }
+
+    /// Returns bit-flag for multiplication operation in right side as `F`
+    fn f_res_mul(&self) -> F {
+        self.flag_at(6)
+    }
+
+    /// Returns bit-flag for program counter update being absolute jump as `F`
+    fn f_pc_abs(&self) -> F {
+        self.flag_at(7)
+    }
+
+    /// Returns bit-flag for program counter update being relative jump as `F`
+    fn f_pc_rel(&self) -> F {
+        self.flag_at(8)
+    }
+
+    /// Returns bit-flag for program counter update being conditional jump as `F`
+    fn f_pc_jnz(&self) -> F {
+        self.flag_at(9)
+    }
+
+    /// Returns bit-flag for allocation counter update being a manual addition as `F`
+    fn f_ap_add(&self) -> F {
+        self.flag_at(10)
+    }
+
+    /// Returns bit-flag for allocation counter update being a self increment as `F`
+    fn f_ap_one(&self) -> F {
+        self.flag_at(11)
+    }
+
+    /// Returns bit-flag for operation being a call as `F`
+    fn f_opc_call(&self) -> F {
+        self.flag_at(12)
+    }
+
+    /// Returns bit-flag for operation being a return as `F`
+    fn f_opc_ret(&self) -> F {
+        self.flag_at(13)
+    }
+
+    /// Returns bit-flag for operation being an assert-equal as `F`
+    fn f_opc_aeq(&self) -> F {
+        self.flag_at(14)
+    }
+
+    /// Returns bit-flag for 16th position
+    fn f15(&self) -> F {
+        self.flag_at(15)
+    }
+
+    /// Returns flagset for destination register
+    fn dst_reg(&self) -> u8 {
+        // dst_reg = fDST_REG
+        flagset(self.flag_bitmask(), 0, 1)
+    }
+
+    /// Returns flagset for first operand register
+    fn op0_reg(&self) -> u8 {
+        // op0_reg = fOP0_REG
+        flagset(self.flag_bitmask(), 1, 1)
+    }
+
+    /// Returns flagset for second operand register
+    fn op1_src(&self) -> u8 {
+        // op1_src = 4*fOP1_AP + 2*fOP1_FP + fOP1_VAL
+        flagset(self.flag_bitmask(), 2, 3)
+    }
+
+    /// Returns flagset for result logics
+    fn res_log(&self) -> u8 {
+        // res_log = 2*fRES_MUL + fRES_ADD
+        flagset(self.flag_bitmask(), 5, 2)
+    }
+
+    /// Returns flagset for program counter update
+    fn pc_up(&self) -> u8 {
+        // pc_up = 4*fPC_JNZ + 2*fPC_REL + fPC_ABS
+        flagset(self.flag_bitmask(), 7, 3)
+    }
+
+    /// Returns flagset for allocation pointer update
+    fn ap_up(&self) -> u8 {
+        // ap_up = 2*fAP_ONE + fAP_ADD
+        flagset(self.flag_bitmask(), 10, 2)
+    }
+
+    /// Returns flagset for operation code
+    fn opcode(&self) -> u8 {
+        // opcode = 4*fOPC_AEQ + 2*fOPC_RET + fOPC_CALL
+        flagset(self.flag_bitmask(), 12, 3)
+    }
+}
+
+impl<F: Field> Decomposition<F> for CairoWord<F> {
+    fn off_dst(&self) -> F {
+        // The least significant 16 bits
+        bias(chunk_u16(self.0, POS_DST))
+    }
+
+    fn off_op0(&self) -> F {
+        // From the 32nd bit to the 17th
+        bias(chunk_u16(self.0, POS_OP0))
+    }
+
+    fn off_op1(&self) -> F {
+        // From the 48th bit to the 33rd
+        bias(chunk_u16(self.0, POS_OP1))
+    }
+
+    fn flag_bitmask(&self) -> u16 {
+        // The most significant 16 bits, all within the first 64 bits of an instruction
+        (low_u64(self.0) >> POS_FLAGS) as u16
+    }
+}
+
+/// Returns the flagset made of the `len` bit-flags of `mask` starting at the `pos`-th one,
+/// the first of them being the least significant
+pub(crate) fn flagset(mask: u16, pos: usize, len: usize) -> u8 {
+    ((mask >> pos) & ((1 << len) - 1)) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::{One, Zero};
+    use mina_curves::pasta::fp::Fp as F;
+
+    #[test]
+    fn test_decomposition_core() {
+        // cell = [ap] = 10 with an immediate
+        let word = CairoWord::new(F::from(0x480680017fff8000u64));
+        assert_eq!(word.off_dst(), F::zero());
+        assert_eq!(word.off_op0(), -F::one());
+        assert_eq!(word.off_op1(), F::one());
+        assert_eq!(word.flag_bitmask(), 0x4806);
+        assert_eq!(word.flag_at(1), F::one());
+        assert_eq!(word.flag_at(NUM_FLAGS), F::zero());
+        assert_eq!(word.flag_at(usize::MAX), F::zero());
+        assert_eq!(word.dst_reg(), DST_AP);
+        assert_eq!(word.op0_reg(), 1);
+        assert_eq!(word.op1_src(), OP1_VAL);
+        assert_eq!(word.res_log(), RES_ONE);
+        assert_eq!(word.pc_up(), PC_SIZ);
+        assert_eq!(word.ap_up(), AP_ONE);
+        assert_eq!(word.opcode(), OPC_AEQ);
+    }
+}
@@ -58,3 +58,23 @@ pub const OPC_CALL: u8 = 1;
 pub const OPC_RET: u8 = 2;
 /// Operation code is an assert-equal
 pub const OPC_AEQ: u8 = 4;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_constants() {
+        // the offsets are 16-bit chunks, counted in chunks, and the flags fill the last chunk
+        const OFFSET_BITS: usize = 16;
+        assert_eq!(NUM_FLAGS, OFFSET_BITS);
+        assert_eq!(POS_FLAGS, 48);
+        let offsets = [POS_DST, POS_OP0, POS_OP1];
+        assert_eq!(offsets, [0, 1, 2]);
+        // the offsets do not overlap and the flags start right after the second operand offset
+        assert!(offsets.windows(2).all(|pair| pair[0] + 1 == pair[1]));
+        assert_eq!(OFFSET_BITS * (POS_OP1 + 1), POS_FLAGS);
+        // an instruction fits in 64 bits
+        assert_eq!(POS_FLAGS + NUM_FLAGS, 64);
+    }
+}
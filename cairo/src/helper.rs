@@ -10,10 +10,10 @@ pub trait CairoFieldHelpers<F> {
     /// Return field element as byte, if it fits. Otherwise returns least significant byte
     fn least_significant_byte(self) -> u8;
 
-    /// Return pos-th 16-bit chunk as another field element
+    /// Return pos-th 16-bit chunk as another field element, where bits beyond the size of the field are zero
     fn chunk_u16(self, pos: usize) -> F;
 
-    /// Return first 64 bits of the field element
+    /// Return first 64 bits of the field element, where bits beyond the size of the field are zero
     fn to_u64(self) -> u64;
 
     /// Return a field element in hexadecimal in little endian
@@ -30,15 +30,16 @@ impl<F: Field> CairoFieldHelpers<F> for F {
 
     fn chunk_u16(self, pos: usize) -> F {
         let bytes = self.to_bytes();
-        let chunk = u16::from(bytes[2 * pos]) + u16::from(bytes[2 * pos + 1]) * 2u16.pow(8);
+        let byte = |i: usize| u16::from(bytes.get(i).copied().unwrap_or(0));
+        let chunk = byte(2 * pos) + byte(2 * pos + 1) * 2u16.pow(8);
         F::from(chunk)
     }
 
     fn to_u64(self) -> u64 {
         let bytes = self.to_bytes();
         let mut acc: u64 = 0;
-        for i in 0..8 {
-            acc += 2u64.pow(i * 8) * (bytes[i as usize] as u64);
+        for (i, byte) in bytes.iter().take(8).enumerate() {
+            acc += 2u64.pow(i as u32 * 8) * (*byte as u64);
         }
         acc
     }
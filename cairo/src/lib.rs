@@ -1,11 +1,24 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs)]
 //! This module contains the code that executes a compiled Cairo program and generates the memory.
 //! The Cairo runner includes code to execute a bytecode compiled Cairo program,
 //! and obtain a memory instantiation after the execution. It uses some code to
 //! represent Cairo instructions and their decomposition, together with their logic
 //! which is represented as steps of computation making up the full program.
+//! Without the default `std` feature, only the [decomposition] of words and the [flags] are available.
+#[cfg(feature = "std")]
+pub mod asm;
+pub mod decomposition;
 pub mod flags;
+#[cfg(feature = "std")]
 pub mod helper;
+#[cfg(feature = "std")]
 pub mod memory;
+#[cfg(feature = "std")]
 pub mod runner;
+#[cfg(all(feature = "std", any(test, feature = "testing")))]
+pub mod testing;
+#[cfg(feature = "std")]
+pub mod witness;
+#[cfg(feature = "std")]
 pub mod word;
@@ -8,6 +8,63 @@ use crate::helper::*;
 use crate::word::CairoWord;
 use ark_ff::Field;
 use core::iter::repeat;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// Errors that can arise when checking the consistency of the memory
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryError<F> {
+    /// Two accesses to the same cell observed different values
+    #[error("cell {addr} was accessed with value {first} and later {second}")]
+    Conflict {
+        /// address of the cell
+        addr: F,
+        /// value observed first
+        first: F,
+        /// conflicting value observed later
+        second: F,
+    },
+    /// A cell was accessed uninitialized after it had been assigned
+    #[error("cell {addr} was assigned {value} and later read uninitialized")]
+    Cleared {
+        /// address of the cell
+        addr: F,
+        /// value assigned first
+        value: F,
+    },
+    /// The final memory does not hold the value observed in the log
+    #[error("cell {addr} was accessed with value {logged} but the memory holds {stored:?}")]
+    Mismatch {
+        /// address of the cell
+        addr: F,
+        /// value observed in the log
+        logged: F,
+        /// content of the memory
+        stored: Option<F>,
+    },
+}
+
+/// Returns the public memory of a program loaded at address `base`, that is
+/// the `(address, value)` pairs of the program segment a proof is bound to
+pub fn public_memory<F: Field>(program: &[CairoWord<F>], base: F) -> Vec<(F, F)> {
+    program
+        .iter()
+        .scan(base, |addr, word| {
+            let entry = (*addr, word.word());
+            *addr += F::one();
+            Some(entry)
+        })
+        .collect()
+}
+
+/// An access to a memory cell performed while running a program
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryAccess<F> {
+    /// address of the accessed cell
+    pub addr: F,
+    /// content of the cell after the access, None if non initialized
+    pub value: Option<F>,
+}
 
 /// This data structure stores the memory of the program
 pub struct CairoMemory<F> {
@@ -94,6 +151,59 @@ impl<F: Field> CairoMemory<F> {
         self.resize(addr.to_u64()); // Resize if necessary
         self[addr].map(|x| x.word())
     }
+
+    /// Replays an access log, such as the one of a run of a program on this memory, and checks
+    /// that every cell was only ever assigned a single value, and that this value is the one
+    /// the memory holds at the end
+    pub fn verify_consistency(&self, access_log: &[MemoryAccess<F>]) -> Result<(), MemoryError<F>> {
+        let mut assigned = HashMap::new();
+        for access in access_log {
+            let addr = access.addr;
+            match (assigned.get(&addr).copied(), access.value) {
+                (None, Some(value)) => {
+                    assigned.insert(addr, value);
+                }
+                (Some(first), Some(second)) if first != second => {
+                    return Err(MemoryError::Conflict {
+                        addr,
+                        first,
+                        second,
+                    })
+                }
+                (Some(value), None) => return Err(MemoryError::Cleared { addr, value }),
+                _ => (),
+            }
+        }
+        for (addr, logged) in assigned {
+            let stored = self
+                .data
+                .get(addr.to_u64() as usize)
+                .copied()
+                .flatten()
+                .map(|x| x.word());
+            if stored != Some(logged) {
+                return Err(MemoryError::Mismatch {
+                    addr,
+                    logged,
+                    stored,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Lists the initialized cells as `addr: value` lines in hexadecimal, sorted by address
+    /// and excluding the 0th dummy entry, to inspect the memory of a failed run
+    pub fn dump(&self) -> String {
+        self.data
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter_map(|(addr, elem)| {
+                elem.map(|elem| format!("{:#06x}: 0x{}\n", addr, elem.word().to_hex_le()))
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -132,4 +242,84 @@ mod tests {
         assert_eq!(6, memory.size() - 1);
         memory.read(F::from(10u32));
     }
+
+    #[test]
+    fn test_dump() {
+        let mut memory = CairoMemory::new(vec![F::from(10u64), F::from(20u64)]);
+        memory.write(F::from(5u32), F::from(50u64));
+        memory.write(F::from(4u32), F::from(40u64));
+        let dump = memory.dump();
+        let lines: Vec<_> = dump.lines().collect();
+        // the dummy entry and the uninitialized cell 3 are not listed
+        assert_eq!(lines.len(), 4);
+        let addrs: Vec<_> = lines.iter().map(|l| l.split(':').next().unwrap()).collect();
+        assert_eq!(addrs, vec!["0x0001", "0x0002", "0x0004", "0x0005"]);
+        assert_eq!(
+            lines[2],
+            format!("0x0004: 0x{}", F::from(40u64).to_hex_le())
+        );
+    }
+
+    #[test]
+    fn test_verify_consistency() {
+        let mut memory = CairoMemory::new(vec![F::from(10u64)]);
+        memory.write(F::from(2u32), F::from(20u64));
+        let access = |addr: u32, value: Option<u64>| MemoryAccess {
+            addr: F::from(addr),
+            value: value.map(F::from),
+        };
+        let log = vec![
+            access(1, Some(10)),
+            access(3, None),
+            access(2, Some(20)),
+            access(1, Some(10)),
+        ];
+        assert_eq!(memory.verify_consistency(&log), Ok(()));
+
+        let log = vec![access(1, Some(10)), access(1, Some(11))];
+        assert_eq!(
+            memory.verify_consistency(&log),
+            Err(MemoryError::Conflict {
+                addr: F::from(1u32),
+                first: F::from(10u32),
+                second: F::from(11u32),
+            })
+        );
+        let log = vec![access(2, Some(20)), access(2, None)];
+        assert!(matches!(
+            memory.verify_consistency(&log),
+            Err(MemoryError::Cleared { .. })
+        ));
+        let log = vec![access(5, Some(50))];
+        assert_eq!(
+            memory.verify_consistency(&log),
+            Err(MemoryError::Mismatch {
+                addr: F::from(5u32),
+                logged: F::from(50u32),
+                stored: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_public_memory() {
+        let instrs = vec![
+            F::from(0x480680017fff8000u64),
+            F::from(10u64),
+            F::from(0x208b7fff7fff7ffeu64),
+        ];
+        let program: Vec<_> = instrs.iter().map(|i| CairoWord::new(*i)).collect();
+        let base = F::from(5u32);
+        let public = public_memory(&program, base);
+        assert_eq!(public.len(), 3);
+        for (i, (addr, value)) in public.iter().enumerate() {
+            assert_eq!(*addr, base + F::from(i as u64));
+            assert_eq!(*value, instrs[i]);
+        }
+        // the memory of the runner loads the program at address 1
+        let memory = CairoMemory::new(instrs);
+        for (addr, value) in public_memory(&program, F::one()) {
+            assert_eq!(memory[addr].unwrap().word(), value);
+        }
+    }
 }
@@ -2,19 +2,47 @@
 //! execution steps, each of which define the execution logic of Cairo instructions
 
 use crate::flags::*;
-use crate::memory::CairoMemory;
+use crate::helper::CairoFieldHelpers;
+use crate::memory::{CairoMemory, MemoryAccess};
+use crate::witness::{verify_trace_step, StepError};
 use crate::word::{CairoWord, Decomposition};
 use ark_ff::Field;
+use std::collections::HashSet;
+use thiserror::Error;
+
+/// Errors that can arise when running a program
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunError<F> {
+    /// The instruction is expected to be an assert-equal
+    #[error("the instruction is not an assert-equal")]
+    NotAssertEq,
+    /// The destination of an assert-equal already holds a value other than the result
+    #[error("assert-equal fails with dst {dst} and res {res}")]
+    AssertEqMismatch {
+        /// content of the destination
+        dst: F,
+        /// result of the instruction
+        res: F,
+    },
+    /// The step with this index does not satisfy the constraints of the circuit
+    #[error("step {step} does not satisfy the circuit: {error}")]
+    InvalidStep {
+        /// index of the step, starting from 0
+        step: u64,
+        /// violated constraint
+        error: StepError,
+    },
+}
 
 /// A structure to store program counter, allocation pointer and frame pointer
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct CairoState<F> {
     /// Program counter: points to address in memory
-    pc: F,
+    pub pc: F,
     /// Allocation pointer: points to first free space in memory
-    ap: F,
+    pub ap: F,
     /// Frame pointer: points to the beginning of the stack in memory (for arguments)
-    fp: F,
+    pub fp: F,
 }
 
 impl<F: Field> CairoState<F> {
@@ -159,20 +187,13 @@ impl<'a, F: Field> CairoStep<'a, F> {
             match self.instr().res_log() {
                 /*0*/
                 RES_ONE => self.vars.res = self.vars.op1, // right part is single operand
-                /*1*/
-                RES_ADD => {
-                    self.vars.res = Some(
-                        self.vars.op0.expect("None op0 after RES_ADD")
-                            + self.vars.op1.expect("None op1 after RES_ADD"),
-                    )
-                } // right part is addition
-                /*2*/
-                RES_MUL => {
-                    self.vars.res = Some(
-                        self.vars.op0.expect("None op0 after RES_MUL")
-                            * self.vars.op1.expect("None op1 after RES_MUL"),
-                    )
-                } // right part is multiplication
+                /*1*/ /*2*/
+                RES_ADD | RES_MUL => {
+                    self.vars.res = Some(self.instr().compute_res(
+                        self.vars.op0.expect("None op0 after RES_ADD or RES_MUL"),
+                        self.vars.op1.expect("None op1 after RES_ADD or RES_MUL"),
+                    ))
+                } // right part is addition or multiplication
                 _ => panic!("Invalid res_log flagset"),
             }
         } else {
@@ -290,6 +311,33 @@ impl<'a, F: Field> CairoStep<'a, F> {
     }
 }
 
+/// A snapshot of an executed step, handed to the callback of [CairoProgram::run_with_callback]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceStep<F> {
+    /// Instruction executed in the step
+    pub instr: CairoWord<F>,
+    /// Program counter before the step
+    pub pc: F,
+    /// Allocation pointer before the step
+    pub ap: F,
+    /// Frame pointer before the step
+    pub fp: F,
+    /// Destination address
+    pub dst_addr: F,
+    /// First operand address
+    pub op0_addr: F,
+    /// Second operand address
+    pub op1_addr: F,
+    /// Destination
+    pub dst: Option<F>,
+    /// First operand
+    pub op0: Option<F>,
+    /// Second operand
+    pub op1: Option<F>,
+    /// Result
+    pub res: Option<F>,
+}
+
 /// This struct stores the needed information to run a program
 pub struct CairoProgram<'a, F> {
     /// total number of steps
@@ -300,21 +348,82 @@ pub struct CairoProgram<'a, F> {
     ini: CairoState<F>,
     /// final computation pointers
     fin: CairoState<F>,
+    /// memory accesses of every step, in order of execution
+    log: Vec<MemoryAccess<F>>,
 }
 
 impl<'a, F: Field> CairoProgram<'a, F> {
-    /// Creates a Cairo execution from the public information (memory and initial pointers)
-    pub fn new(mem: &mut CairoMemory<F>, pc: u64, ap: u64) -> CairoProgram<F> {
-        let mut prog = CairoProgram {
+    /// Creates a Cairo execution that has not run any step yet, from the memory and initial pointers
+    fn init(mem: &mut CairoMemory<F>, pc: u64, ap: u64) -> CairoProgram<F> {
+        CairoProgram {
             steps: F::zero(),
             mem,
             ini: CairoState::new(F::from(pc), F::from(ap), F::from(ap)),
             fin: CairoState::new(F::zero(), F::zero(), F::zero()),
-        };
-        prog.execute();
+            log: Vec::new(),
+        }
+    }
+
+    /// Creates a Cairo execution from the public information (memory and initial pointers)
+    pub fn new(mem: &mut CairoMemory<F>, pc: u64, ap: u64) -> CairoProgram<F> {
+        let mut prog = Self::init(mem, pc, ap);
+        prog.execute(None, &mut |_| true);
         prog
     }
 
+    /// Creates a Cairo execution like [CairoProgram::new], but stops after at most `max_steps` steps
+    /// and invokes `on_step` after each of them. This is meant for tracing and debugging a run.
+    pub fn run_with_callback(
+        mem: &mut CairoMemory<F>,
+        pc: u64,
+        ap: u64,
+        max_steps: u64,
+        mut on_step: impl FnMut(&TraceStep<F>),
+    ) -> CairoProgram<F> {
+        let mut prog = Self::init(mem, pc, ap);
+        prog.execute(Some(max_steps), &mut |step: &TraceStep<F>| {
+            on_step(step);
+            true
+        });
+        prog
+    }
+
+    /// Creates a Cairo execution like [CairoProgram::run_with_callback], but lays each step into
+    /// the witness columns of the circuit and checks it with [verify_trace_step], stopping at the
+    /// first step the circuit would reject. This catches divergences between the runner and the circuit.
+    pub fn run_verified(
+        mem: &mut CairoMemory<F>,
+        pc: u64,
+        ap: u64,
+        max_steps: u64,
+    ) -> Result<CairoProgram<F>, RunError<F>> {
+        Self::run_checked(mem, pc, ap, max_steps, verify_trace_step)
+    }
+
+    /// Runs the program and checks every step with `check`, stopping at the first failing one
+    fn run_checked(
+        mem: &mut CairoMemory<F>,
+        pc: u64,
+        ap: u64,
+        max_steps: u64,
+        mut check: impl FnMut(&TraceStep<F>) -> Result<(), StepError>,
+    ) -> Result<CairoProgram<F>, RunError<F>> {
+        let mut prog = Self::init(mem, pc, ap);
+        let mut failure = None;
+        let mut step = 0;
+        prog.execute(Some(max_steps), &mut |trace_step: &TraceStep<F>| {
+            if let Err(error) = check(trace_step) {
+                failure = Some(RunError::InvalidStep { step, error });
+            }
+            step += 1;
+            failure.is_none()
+        });
+        match failure {
+            Some(error) => Err(error),
+            None => Ok(prog),
+        }
+    }
+
     /// Outputs the total number of steps of the execution carried out by the runner
     pub fn get_steps(&self) -> F {
         self.steps
@@ -325,9 +434,38 @@ impl<'a, F: Field> CairoProgram<'a, F> {
         self.fin
     }
 
+    /// Outputs the memory accesses of the execution: instruction, destination and operands of each step
+    pub fn get_access_log(&self) -> &[MemoryAccess<F>] {
+        &self.log
+    }
+
+    /// Outputs the number of distinct memory cells accessed by the execution
+    pub fn get_distinct_cells(&self) -> usize {
+        self.log
+            .iter()
+            .map(|access| access.addr)
+            .collect::<HashSet<F>>()
+            .len()
+    }
+
+    /// Outputs the length of the output segment written by the program.
+    /// Assumes the output builtin is the only implicit argument of `main`, so that the output
+    /// pointer is passed at `[fp - 3]` of the initial frame and returned at `[ap - 1]` of the final one
+    pub fn get_output_len(&self) -> u64 {
+        let begin = self.mem[self.ini.fp - F::from(3u32)]
+            .expect("Missing beginning of output")
+            .word();
+        let end = self.mem[self.fin.ap - F::one()]
+            .expect("Missing end of output")
+            .word();
+        (end - begin).to_u64()
+    }
+
     /// This function simulates an execution of the Cairo program received as input.
-    /// It generates the full memory stack and the execution trace
-    fn execute(&mut self) {
+    /// It generates the full memory stack and the execution trace, stopping after
+    /// `max_steps` steps if given, and calling `on_step` after every step.
+    /// The execution also stops after a step for which `on_step` returns false.
+    fn execute(&mut self, max_steps: Option<u64>, on_step: &mut impl FnMut(&TraceStep<F>) -> bool) {
         // set finishing flag to false, as it just started
         let mut end = false;
         // saves local copy of the initial (claimed) pointers of the program
@@ -344,6 +482,29 @@ impl<'a, F: Field> CairoProgram<'a, F> {
             // execute current step and increase time counter
             step.execute();
             n += 1;
+            // record the memory cells used by the step
+            for addr in [
+                step.curr.pc,
+                step.vars.dst_addr,
+                step.vars.op0_addr,
+                step.vars.op1_addr,
+            ] {
+                let value = step.mem.read(addr);
+                self.log.push(MemoryAccess { addr, value });
+            }
+            let proceed = on_step(&TraceStep {
+                instr: step.instr(),
+                pc: step.curr.pc,
+                ap: step.curr.ap,
+                fp: step.curr.fp,
+                dst_addr: step.vars.dst_addr,
+                op0_addr: step.vars.op0_addr,
+                op1_addr: step.vars.op1_addr,
+                dst: step.vars.dst,
+                op0: step.vars.op0,
+                op1: step.vars.op1,
+                res: step.vars.res,
+            });
             match step.next {
                 None => end = true, // if find no next pointers, end
                 _ => {
@@ -355,6 +516,10 @@ impl<'a, F: Field> CairoProgram<'a, F> {
                         // if reading from unallocated memory, end
                         end = true;
                     }
+                    if max_steps == Some(n) || !proceed {
+                        // if the step limit was reached or the callback asks to, end
+                        end = true;
+                    }
                 }
             }
         }
@@ -367,6 +532,7 @@ impl<'a, F: Field> CairoProgram<'a, F> {
 mod tests {
     use super::*;
     use crate::helper::CairoFieldHelpers;
+    use crate::word::{relocate_program, WordError};
     use mina_curves::pasta::fp::Fp as F;
 
     #[test]
@@ -408,6 +574,77 @@ mod tests {
         mem.write(F::from(5u32), F::from(7u32)); //end of output
         let prog = CairoProgram::new(&mut mem, 1, 6);
         println!("{}", prog.mem);
+        // the tempvar accesses 1, 2, 5, 6 and the return accesses 3, 4, 5
+        assert_eq!(prog.get_steps(), F::from(2u32));
+        assert_eq!(prog.get_access_log().len(), 8);
+        assert_eq!(prog.get_distinct_cells(), 6);
+        assert_eq!(prog.mem.verify_consistency(prog.get_access_log()), Ok(()));
+    }
+
+    #[test]
+    fn test_run_verified() {
+        let (program, ini, expected) = crate::testing::sample_program::<F>();
+        let words: Vec<F> = program.iter().map(|w| w.word()).collect();
+
+        let mut mem = CairoMemory::new(words.clone());
+        let prog = CairoProgram::run_verified(&mut mem, ini.pc.to_u64(), ini.ap.to_u64(), 100)
+            .expect("the runner and the circuit agree");
+        assert_eq!(prog.get_steps(), F::from(expected.steps));
+        assert_eq!(prog.get_final(), expected.fin);
+
+        // a step whose result is corrupted before being laid out is caught
+        let mut mem = CairoMemory::new(words);
+        let res =
+            CairoProgram::run_checked(&mut mem, ini.pc.to_u64(), ini.ap.to_u64(), 100, |step| {
+                let mut step = *step;
+                if step.pc == F::from(10u32) {
+                    step.res = step.res.map(|res| res + F::from(1u32));
+                }
+                verify_trace_step(&step)
+            });
+        // pc 10 is first reached at the 5th step
+        assert_eq!(
+            res.err(),
+            Some(RunError::InvalidStep {
+                step: 4,
+                error: StepError::Result
+            })
+        );
+    }
+
+    #[test]
+    fn test_run_with_callback() {
+        let instrs = vec![
+            F::from(0x480680017fff8000u64),
+            F::from(10u64),
+            F::from(0x208b7fff7fff7ffeu64),
+        ];
+        let mut mem = CairoMemory::<F>::new(instrs.clone());
+        mem.write(F::from(4u32), F::from(7u32));
+        mem.write(F::from(5u32), F::from(7u32));
+        let mut trace = vec![];
+        let prog = CairoProgram::run_with_callback(&mut mem, 1, 6, 10, |step| trace.push(*step));
+        assert_eq!(prog.get_steps(), F::from(2u32));
+        let regs: Vec<_> = trace.iter().map(|s| (s.pc, s.ap, s.fp)).collect();
+        assert_eq!(
+            regs,
+            vec![
+                (F::from(1u32), F::from(6u32), F::from(6u32)),
+                (F::from(3u32), F::from(7u32), F::from(6u32)),
+            ]
+        );
+        // the tempvar writes the immediate to [ap]
+        assert_eq!(trace[0].instr.word(), instrs[0]);
+        assert_eq!(trace[0].dst_addr, F::from(6u32));
+        assert_eq!(trace[0].res, Some(F::from(10u32)));
+
+        // the run halts after the maximum number of steps
+        let mut mem = CairoMemory::<F>::new(instrs);
+        let mut calls = 0;
+        let prog = CairoProgram::run_with_callback(&mut mem, 1, 6, 1, |_| calls += 1);
+        assert_eq!(calls, 1);
+        assert_eq!(prog.get_steps(), F::from(1u32));
+        assert_eq!(prog.get_final().pc, F::from(1u32));
     }
 
     #[test]
@@ -485,4 +722,104 @@ mod tests {
         assert_eq!(prog.mem.read(F::from(42u32)).unwrap(), F::from(20u32));
         assert_eq!(prog.mem.read(F::from(43u32)).unwrap(), F::from(410u32));
     }
+
+    #[test]
+    fn test_output_len() {
+        /*
+        %builtins output
+        from starkware.cairo.common.serialize import serialize_word
+        func main{output_ptr : felt*}():
+            tempvar x = 10
+            tempvar y = x + x
+            tempvar z = y * y + x
+            serialize_word(x)
+            serialize_word(y)
+            return ()
+        end
+        */
+        let instrs: Vec<i128> = vec![
+            0x400380007ffc7ffd,
+            0x482680017ffc8000,
+            1,
+            0x208b7fff7fff7ffe,
+            0x480680017fff8000,
+            10,
+            0x48307fff7fff8000,
+            0x48507fff7fff8000,
+            0x48307ffd7fff8000,
+            0x480a7ffd7fff8000,
+            0x48127ffb7fff8000,
+            0x1104800180018000,
+            -11,
+            0x48127ff87fff8000,
+            0x1104800180018000,
+            -14,
+            0x208b7fff7fff7ffe,
+        ];
+        let mut mem = CairoMemory::<F>::new(F::vec_to_field(&instrs));
+        mem.write(F::from(18u32), F::from(34u32)); // beginning of outputs
+        mem.write(F::from(19u32), F::from(36u32)); // end of outputs
+        mem.write(F::from(20u32), F::from(36u32)); // end of program
+        let prog = CairoProgram::new(&mut mem, 5, 21);
+        assert_eq!(prog.get_final().ap, F::from(34u32));
+        assert_eq!(prog.get_output_len(), 2);
+        assert_eq!(prog.mem.read(F::from(34u32)).unwrap(), F::from(10u32));
+        assert_eq!(prog.mem.read(F::from(35u32)).unwrap(), F::from(20u32));
+    }
+
+    #[test]
+    fn test_relocated_program() {
+        // This program is compiled to start at address 0
+        //    tempvar x = 10
+        //    jmp abs 6
+        //    tempvar x = 20
+        //    tempvar x = 30
+        //    return()
+        let instrs = vec![
+            F::from(0x480680017fff8000u64),
+            F::from(10u64),
+            F::from(0x008780017fff7fffu64),
+            F::from(6u64),
+            F::from(0x480680017fff8000u64),
+            F::from(20u64),
+            F::from(0x480680017fff8000u64),
+            F::from(30u64),
+            F::from(0x208b7fff7fff7ffeu64),
+        ];
+        let program: Vec<_> = instrs.into_iter().map(CairoWord::new).collect();
+
+        let base = 100u64;
+        let relocated = relocate_program(&program, F::from(base)).unwrap();
+        // only the target of the absolute jump changes
+        assert_eq!(relocated[3].word(), F::from(base + 6));
+        for (i, (word, orig)) in relocated.iter().zip(program.iter()).enumerate() {
+            if i != 3 {
+                assert_eq!(word, orig);
+            }
+        }
+
+        let mut mem = CairoMemory::<F>::new(vec![]);
+        for (i, word) in relocated.iter().enumerate() {
+            mem.write(F::from(base + i as u64), word.word());
+        }
+        let ap = base + 11;
+        mem.write(F::from(ap - 2), F::from(ap)); // previous frame pointer
+        mem.write(F::from(ap - 1), F::from(ap + 2)); // return address
+        let prog = CairoProgram::new(&mut mem, base, ap);
+        assert_eq!(prog.get_final().pc, F::from(base + 8));
+        assert_eq!(prog.get_final().ap, F::from(ap + 2));
+        assert_eq!(prog.get_final().fp, F::from(ap));
+        // the jump skipped the second tempvar
+        assert_eq!(prog.mem.read(F::from(ap)).unwrap(), F::from(10u64));
+        assert_eq!(prog.mem.read(F::from(ap + 1)).unwrap(), F::from(30u64));
+    }
+
+    #[test]
+    fn test_relocate_missing_immediate() {
+        let program = vec![CairoWord::new(F::from(0x008780017fff7fffu64))];
+        assert_eq!(
+            relocate_program(&program, F::from(100u64)),
+            Err(WordError::MissingImmediate(0))
+        );
+    }
 }
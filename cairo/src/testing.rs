@@ -0,0 +1,106 @@
+//! This module contains fixtures to test the Cairo runner end to end,
+//! available under the `testing` feature.
+
+use crate::runner::CairoState;
+use crate::word::CairoWord;
+use ark_ff::Field;
+
+/// Registers of a Cairo run
+pub type Registers<F> = CairoState<F>;
+
+/// The expected result of running a program
+pub struct ExpectedOutput<F> {
+    /// total number of steps
+    pub steps: u64,
+    /// registers at the beginning of the last step
+    pub fin: Registers<F>,
+    /// `(address, value)` pairs of the execution memory
+    pub memory: Vec<(F, F)>,
+}
+
+/// Returns a small program using calls, returns, assert-equals and conditional jumps,
+/// together with its initial registers and its expected output.
+/// The words are meant to be loaded from address 1, and end with the return frame of `main`.
+///
+/// ```ignore
+/// func main():
+///     tempvar n = 3
+///     countdown(n)
+///     return ()
+/// end
+///
+/// func countdown(n):
+///     [ap] = [fp - 3]; ap++
+///     loop:
+///     jmp dec if [ap - 1] != 0
+///     return ()
+///     dec:
+///     [ap] = [ap - 1] - 1; ap++
+///     jmp loop
+/// end
+/// ```
+pub fn sample_program<F: Field>() -> (Vec<CairoWord<F>>, Registers<F>, ExpectedOutput<F>) {
+    let words: Vec<i128> = vec![
+        0x480680017fff8000, // 1: tempvar n = 3
+        3,
+        0x1104800180018000, // 3: call rel 3
+        3,
+        0x208b7fff7fff7ffe, // 5: ret
+        0x480a7ffd7fff8000, // 6: [ap] = [fp - 3]; ap++
+        0x020680017fff7fff, // 7: jmp rel 3 if [ap - 1] != 0
+        3,
+        0x208b7fff7fff7ffe, // 9: ret
+        0x482480017fff8000, // 10: [ap] = [ap - 1] + (-1); ap++
+        -1,
+        0x010780017fff7fff, // 12: jmp rel -5
+        -5,
+        16, // 14: frame pointer of the caller of main
+        23, // 15: return address of main
+    ];
+    let program = words
+        .iter()
+        .map(|w| {
+            let abs = F::from(w.unsigned_abs());
+            CairoWord::new(if *w < 0 { -abs } else { abs })
+        })
+        .collect();
+    let ini = Registers::new(F::from(1u32), F::from(16u32), F::from(16u32));
+    let expected = ExpectedOutput {
+        steps: 15,
+        fin: Registers::new(F::from(5u32), F::from(23u32), F::from(16u32)),
+        memory: [
+            (16, 3), // n
+            (17, 16),
+            (18, 5), // frame of countdown
+            (19, 3),
+            (20, 2),
+            (21, 1),
+            (22, 0), // countdown
+        ]
+        .iter()
+        .map(|(addr, value)| (F::from(*addr as u64), F::from(*value as u64)))
+        .collect(),
+    };
+    (program, ini, expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helper::CairoFieldHelpers;
+    use crate::memory::CairoMemory;
+    use crate::runner::CairoProgram;
+    use mina_curves::pasta::fp::Fp as F;
+
+    #[test]
+    fn test_sample_program() {
+        let (program, ini, expected) = sample_program::<F>();
+        let mut mem = CairoMemory::new(program.iter().map(|w| w.word()).collect());
+        let prog = CairoProgram::new(&mut mem, ini.pc.to_u64(), ini.ap.to_u64());
+        assert_eq!(prog.get_steps(), F::from(expected.steps));
+        assert_eq!(prog.get_final(), expected.fin);
+        for (addr, value) in expected.memory {
+            assert_eq!(mem.read(addr), Some(value));
+        }
+    }
+}
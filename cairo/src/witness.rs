@@ -0,0 +1,214 @@
+//! This module lays the trace of a run of a Cairo program into the witness columns
+//! expected by the Kimchi Cairo gates, using two rows per executed instruction.
+//!
+//! | Row  |  0 |  1 |  2 |     3 |       4 |       5 |       6 |     7 |     8 |     9 |  10 |  11 |  12 |  13 |   14 |
+//! | ------------------------------------------------------------------------------------------------------------------ |
+//! |   2i | pc | ap | fp | instr | off_dst | off_op0 | off_op1 | a_dst | a_op0 | a_op1 | dst | op0 | op1 | res | size |
+//! | 2i+1 | f0 | f1 | f2 |    f3 |      f4 |      f5 |      f6 |    f7 |    f8 |    f9 | f10 | f11 | f12 | f13 |  f14 |
+//!
+//! Offsets are the signed values returned by [Decomposition], `size` is 2 when the instruction is followed by
+//! an immediate and 1 otherwise, and values left undefined by the step are laid as zero.
+
+use crate::flags::NUM_FLAGS;
+use crate::runner::TraceStep;
+use crate::word::Decomposition;
+use ark_ff::Field;
+use thiserror::Error;
+
+/// Constraints of a step of the trace that can be violated by its rows of the witness
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepError {
+    /// The bit-flag at this position is not a bit
+    #[error("bit-flag {0} is not boolean")]
+    NonBooleanFlag(usize),
+    /// The instruction is not made of the offsets and bit-flags
+    #[error("the instruction does not match its offsets and bit-flags")]
+    Instruction,
+    /// The destination address does not follow from the registers and offset
+    #[error("invalid destination address")]
+    DstAddress,
+    /// The first operand address does not follow from the registers and offset
+    #[error("invalid first operand address")]
+    Op0Address,
+    /// The second operand address does not follow from the registers, first operand and offset
+    #[error("invalid second operand address")]
+    Op1Address,
+    /// The size of the instruction does not follow from its bit-flags
+    #[error("invalid instruction size")]
+    Size,
+    /// The result is not the combination of the operands given by its bit-flags
+    #[error("invalid result")]
+    Result,
+}
+
+/// Number of witness columns of the Kimchi circuits
+pub const COLUMNS: usize = 15;
+
+/// Number of witness rows filled for each step of the trace
+pub const ROWS_PER_STEP: usize = 2;
+
+/// Lays every step of the trace into its two rows of the witness
+pub fn cairo_trace_to_witness<F: Field>(trace: &[TraceStep<F>]) -> [Vec<F>; COLUMNS] {
+    let mut witness = [(); COLUMNS].map(|_| vec![F::zero(); ROWS_PER_STEP * trace.len()]);
+    for (i, step) in trace.iter().enumerate() {
+        let size = if step.instr.uses_immediate() {
+            F::from(2u32)
+        } else {
+            F::one()
+        };
+        let registers = [
+            step.pc,
+            step.ap,
+            step.fp,
+            step.instr.word(),
+            step.instr.off_dst(),
+            step.instr.off_op0(),
+            step.instr.off_op1(),
+            step.dst_addr,
+            step.op0_addr,
+            step.op1_addr,
+            step.dst.unwrap_or_else(F::zero),
+            step.op0.unwrap_or_else(F::zero),
+            step.op1.unwrap_or_else(F::zero),
+            step.res.unwrap_or_else(F::zero),
+            size,
+        ];
+        let flags = step.instr.flags_array();
+        for (col, (reg, flag)) in witness.iter_mut().zip(registers.iter().zip(flags)) {
+            col[ROWS_PER_STEP * i] = *reg;
+            col[ROWS_PER_STEP * i + 1] = flag;
+        }
+    }
+    witness
+}
+
+/// Checks the constraints of the Cairo instruction laid out on the two rows of the witness
+/// starting at `row`, which only involve the step itself. The constraints spanning consecutive
+/// steps, such as the update of the registers, and the ones involving the memory are not checked.
+pub fn verify_step_rows<F: Field>(
+    witness: &[Vec<F>; COLUMNS],
+    row: usize,
+) -> Result<(), StepError> {
+    let cell = |col: usize| witness[col][row];
+    let flag = |i: usize| witness[i][row + 1];
+    let (pc, ap, fp, instr) = (cell(0), cell(1), cell(2), cell(3));
+    let (off_dst, off_op0, off_op1) = (cell(4), cell(5), cell(6));
+    let (dst_addr, op0_addr, op1_addr) = (cell(7), cell(8), cell(9));
+    let (op0, op1, res, size) = (cell(11), cell(12), cell(13), cell(14));
+
+    if let Some(i) = (0..NUM_FLAGS - 1).find(|i| flag(*i).square() != flag(*i)) {
+        return Err(StepError::NonBooleanFlag(i));
+    }
+    let flags = (0..NUM_FLAGS - 1)
+        .rev()
+        .fold(F::zero(), |acc, i| acc.double() + flag(i));
+    let bias = F::from(1u64 << 15);
+    let recomposed = (off_dst + bias)
+        + F::from(1u64 << 16) * (off_op0 + bias)
+        + F::from(1u64 << 32) * (off_op1 + bias)
+        + F::from(1u64 << 48) * flags;
+    if instr != recomposed {
+        return Err(StepError::Instruction);
+    }
+    let one = F::one();
+    if dst_addr != flag(0) * fp + (one - flag(0)) * ap + off_dst {
+        return Err(StepError::DstAddress);
+    }
+    if op0_addr != flag(1) * fp + (one - flag(1)) * ap + off_op0 {
+        return Err(StepError::Op0Address);
+    }
+    let op1_base =
+        flag(2) * pc + flag(3) * fp + flag(4) * ap + (one - flag(2) - flag(3) - flag(4)) * op0;
+    if op1_addr != op1_base + off_op1 {
+        return Err(StepError::Op1Address);
+    }
+    if size != flag(2) + one {
+        return Err(StepError::Size);
+    }
+    // the result of a conditional jump is unused
+    let combined = (one - flag(5) - flag(6)) * op1 + flag(5) * (op0 + op1) + flag(6) * op0 * op1;
+    if (one - flag(9)) * (res - combined) != F::zero() {
+        return Err(StepError::Result);
+    }
+    Ok(())
+}
+
+/// Lays a step into the witness and checks its constraints with [verify_step_rows]
+pub fn verify_trace_step<F: Field>(step: &TraceStep<F>) -> Result<(), StepError> {
+    verify_step_rows(&cairo_trace_to_witness(&[*step]), 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::CairoMemory;
+    use crate::runner::CairoProgram;
+    use mina_curves::pasta::fp::Fp as F;
+
+    #[test]
+    fn test_trace_to_witness() {
+        // tempvar x = 10; return()
+        let instrs = vec![
+            F::from(0x480680017fff8000u64),
+            F::from(10u64),
+            F::from(0x208b7fff7fff7ffeu64),
+        ];
+        let mut mem = CairoMemory::<F>::new(instrs.clone());
+        mem.write(F::from(4u32), F::from(7u32));
+        mem.write(F::from(5u32), F::from(7u32));
+        let mut trace = vec![];
+        CairoProgram::run_with_callback(&mut mem, 1, 6, 10, |step| trace.push(*step));
+        assert_eq!(trace.len(), 2);
+
+        let witness = cairo_trace_to_witness(&trace);
+        assert!(witness.iter().all(|col| col.len() == 4));
+
+        // registers, instruction and result of the tempvar
+        assert_eq!(witness[0][0], F::from(1u32));
+        assert_eq!(witness[1][0], F::from(6u32));
+        assert_eq!(witness[3][0], instrs[0]);
+        assert_eq!(witness[7][0], F::from(6u32));
+        assert_eq!(witness[13][0], F::from(10u32));
+        assert_eq!(witness[14][0], F::from(2u32));
+        // the return is one word long and starts at pc 3
+        assert_eq!(witness[0][2], F::from(3u32));
+        assert_eq!(witness[4][2], -F::from(2u32));
+        assert_eq!(witness[14][2], F::from(1u32));
+        // flags of both instructions
+        for (row, step) in [1, 3].iter().zip(&trace) {
+            let flags = step.instr.flags_array();
+            for (col, flag) in flags.iter().take(COLUMNS).enumerate() {
+                assert_eq!(witness[col][*row], *flag);
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_step_rows() {
+        let instrs = vec![
+            F::from(0x480680017fff8000u64),
+            F::from(10u64),
+            F::from(0x208b7fff7fff7ffeu64),
+        ];
+        let mut mem = CairoMemory::<F>::new(instrs);
+        mem.write(F::from(4u32), F::from(7u32));
+        mem.write(F::from(5u32), F::from(7u32));
+        let mut trace = vec![];
+        CairoProgram::run_with_callback(&mut mem, 1, 6, 10, |step| trace.push(*step));
+        let mut witness = cairo_trace_to_witness(&trace);
+        assert_eq!(verify_step_rows(&witness, 0), Ok(()));
+        assert_eq!(verify_step_rows(&witness, 2), Ok(()));
+
+        witness[5][1] = F::from(2u32);
+        assert_eq!(
+            verify_step_rows(&witness, 0),
+            Err(StepError::NonBooleanFlag(5))
+        );
+        witness[5][1] = F::from(0u32);
+        witness[13][0] += F::from(1u32);
+        assert_eq!(verify_step_rows(&witness, 0), Err(StepError::Result));
+        // the immediate is read right after the instruction
+        witness[0][0] += F::from(1u32);
+        assert_eq!(verify_step_rows(&witness, 0), Err(StepError::Op1Address));
+    }
+}
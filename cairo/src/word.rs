@@ -3,261 +3,977 @@
 //! This is the hexadecimal value for 2 ^ 251 + 17 * 2 ^ 192 + 1
 //! Our Pallas curves have 255 bits, so Cairo native instructions will fit.
 //! This means that our Cairo implementation can admit a larger domain for immediate values than theirs.
+//! The decomposition of instructions only looks at their first 64 bits, so it works for any field
+//! large enough to hold them, such as a 63-bit prime field for testing.
 
+pub use crate::decomposition::{CairoWord, Decomposition, Flagsets};
+
+use crate::decomposition::bias;
 use crate::flags::*;
 use crate::helper::CairoFieldHelpers;
+use crate::runner::RunError;
 use ark_ff::Field;
 use o1_utils::field_helpers::FieldHelpers;
-
-/// A Cairo word for the runner. Some words are instructions (which fit inside a `u64`). Others are immediate values (any `F` element).
-#[derive(Clone, Copy)]
-pub struct CairoWord<F>(F);
-
-/// Returns an offset of 16 bits to its biased representation in the interval `[-2^15,2^15)` as a field element
-fn bias<F: Field>(offset: F) -> F {
-    offset - F::from(2u16.pow(15u32)) // -2^15 + sum_(i=0..15) b_i * 2^i
+use std::collections::HashSet;
+use thiserror::Error;
+
+/// Errors that can arise when handling Cairo words
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordError {
+    /// The instruction at this position of the program uses an immediate value, but it is the last word
+    #[error("missing immediate value after the instruction at position {0}")]
+    MissingImmediate(usize),
+    /// The result logic is not allowed for the operation code
+    #[error("invalid res_log {res_log} for opcode {opcode}")]
+    InvalidResOpcode {
+        /// flagset of the operation code
+        opcode: u8,
+        /// flagset of the result logic
+        res_log: u8,
+    },
+    /// A group of flags takes a value that is not allowed for the operation code
+    #[error("{flagset} flagset {value} not allowed for opcode {opcode}")]
+    InvalidOpcodeFlagset {
+        /// flagset of the operation code
+        opcode: u8,
+        /// name of the group of flags
+        flagset: &'static str,
+        /// flagset of the group of flags
+        value: u8,
+    },
+    /// A group of flags takes a value that is not a valid flagset
+    #[error("invalid {0} flagset {1}")]
+    InvalidFlagset(&'static str, u8),
+    /// The immediate value does not fit in the allowed number of bits
+    #[error("immediate value of {bits} bits exceeds the maximum of {max_bits} bits")]
+    ImmediateTooWide {
+        /// number of bits of the immediate value
+        bits: u32,
+        /// maximum number of bits allowed
+        max_bits: u32,
+    },
+    /// The offset does not lie in the interval `[-2^15,2^15)`
+    #[error("offset {0} out of range")]
+    OffsetOutOfRange(i32),
+    /// The word does not fit in 64 bits, so it is an immediate value rather than an instruction
+    #[error("not an instruction")]
+    NotInstruction,
+    /// The JSON form of an instruction misses this field or holds an invalid value in it
+    #[error("invalid JSON field {0}")]
+    InvalidJson(&'static str),
+    /// The bytes are not the serialization of a field element
+    #[error("invalid byte encoding of a word")]
+    InvalidBytes,
 }
 
-impl<F: Field> CairoWord<F> {
-    /// Creates a [CairoWord] from a field element
-    pub fn new(word: F) -> CairoWord<F> {
-        CairoWord(word)
-    }
-
-    /// Returns the content of the word as a field element
-    pub fn word(&self) -> F {
-        self.0
+/// Names of the 15 bit-flags of an instruction in the JSON form of [CairoWord], in order
+const FLAG_NAMES: [&str; NUM_FLAGS - 1] = [
+    "dst_fp", "op0_fp", "op1_val", "op1_fp", "op1_ap", "res_add", "res_mul", "pc_abs", "pc_rel",
+    "pc_jnz", "ap_add", "ap_one", "opc_call", "opc_ret", "opc_aeq",
+];
+
+/// Returns the name of an operation code in the JSON form of [CairoWord]
+fn opcode_name(opcode: u8) -> &'static str {
+    match opcode {
+        OPC_JMP_INC => "jmp_inc",
+        OPC_CALL => "call",
+        OPC_RET => "ret",
+        OPC_AEQ => "assert_eq",
+        _ => "invalid",
     }
 }
 
-/// This trait contains methods that decompose a field element into [CairoWord] components
-pub trait Decomposition<F> {
-    /// Returns the destination offset in biased representation
-    fn off_dst(&self) -> F;
-
-    /// Returns the first operand offset in biased representation
-    fn off_op0(&self) -> F;
+/// A decoded field of an instruction that differs between two [CairoWord]s,
+/// holding the value in the first word followed by the value in the second one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldDiff<F> {
+    /// Destination offset in biased representation
+    OffDst(F, F),
+    /// First operand offset in biased representation
+    OffOp0(F, F),
+    /// Second operand offset in biased representation
+    OffOp1(F, F),
+    /// Flagset for destination register
+    DstReg(u8, u8),
+    /// Flagset for first operand register
+    Op0Reg(u8, u8),
+    /// Flagset for second operand source
+    Op1Src(u8, u8),
+    /// Flagset for result logics
+    ResLog(u8, u8),
+    /// Flagset for program counter update
+    PcUp(u8, u8),
+    /// Flagset for allocation pointer update
+    ApUp(u8, u8),
+    /// Flagset for operation code
+    Opcode(u8, u8),
+    /// Bit-flag in 16th position
+    F15(F, F),
+}
 
-    /// Returns the second operand offset in biased representation
-    fn off_op1(&self) -> F;
+/// Operation code of an instruction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Opcode {
+    /// Jump or increment
+    JmpInc,
+    /// Call
+    Call,
+    /// Return
+    Ret,
+    /// Assert-equal
+    AssertEq,
+}
 
-    /// Returns vector of 16 flags
-    fn flags(&self) -> Vec<F>;
+/// Kind of an instruction given by its operation code flagset, where the empty flagset of
+/// jumps and increments is a `Nop`, as the instruction neither calls, returns nor asserts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InstructionKind {
+    /// Call
+    Call,
+    /// Return
+    Ret,
+    /// Assert-equal
+    AssertEq,
+    /// No operation besides the updates of the registers
+    Nop,
+}
 
-    /// Returns i-th bit-flag
-    fn flag_at(&self, pos: usize) -> F;
+/// Base of the address of an operand: a register, the first operand for double indexing,
+/// or the program counter for immediate values
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Base {
+    /// Allocation pointer
+    Ap,
+    /// Frame pointer
+    Fp,
+    /// Program counter, used by immediate values
+    Pc,
+    /// Content of the first operand, used by double indexing
+    Op0,
+}
 
-    /// Returns bit-flag for destination register as `F`
-    fn f_dst_fp(&self) -> F;
+/// Address of an operand, as a base plus a signed offset
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Address {
+    /// base of the address
+    pub base: Base,
+    /// offset from the base
+    pub off: i16,
+}
 
-    /// Returns bit-flag for first operand register as `F`
-    fn f_op0_fp(&self) -> F;
+/// A structured view of an instruction of a program
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodedInstruction<F> {
+    /// operation code
+    pub opcode: Opcode,
+    /// address of the destination
+    pub dst: Address,
+    /// address of the first operand
+    pub op0: Address,
+    /// address of the second operand
+    pub op1: Address,
+    /// flagset for result logics
+    pub res_log: u8,
+    /// flagset for program counter update
+    pub pc_up: u8,
+    /// flagset for allocation pointer update
+    pub ap_up: u8,
+    /// immediate value following the instruction, if it uses one
+    pub immediate: Option<F>,
+}
 
-    /// Returns bit-flag for immediate value for second register as `F`
-    fn f_op1_val(&self) -> F;
+/// Decodes every instruction of a program into a [DecodedInstruction], resolving the immediate
+/// values from the word following the instructions that use them.
+pub fn decode_program<F: Field>(
+    program: &[CairoWord<F>],
+) -> Result<Vec<DecodedInstruction<F>>, WordError> {
+    let mut decoded = vec![];
+    let mut pos = 0;
+    while pos < program.len() {
+        let instr = program[pos];
+        let reg = |flag: u8| if flag == 0 { Base::Ap } else { Base::Fp };
+        let op1_base = match instr.op1_src() {
+            OP1_DBL => Base::Op0,
+            OP1_VAL => Base::Pc,
+            OP1_FP => Base::Fp,
+            OP1_AP => Base::Ap,
+            other => return Err(WordError::InvalidFlagset("op1_src", other)),
+        };
+        let opcode = match instr.opcode() {
+            OPC_JMP_INC => Opcode::JmpInc,
+            OPC_CALL => Opcode::Call,
+            OPC_RET => Opcode::Ret,
+            OPC_AEQ => Opcode::AssertEq,
+            other => return Err(WordError::InvalidFlagset("opcode", other)),
+        };
+        let (res_log, pc_up, ap_up) = (instr.res_log(), instr.pc_up(), instr.ap_up());
+        if !matches!(res_log, RES_ONE | RES_ADD | RES_MUL) {
+            return Err(WordError::InvalidFlagset("res_log", res_log));
+        }
+        if !matches!(pc_up, PC_SIZ | PC_ABS | PC_REL | PC_JNZ) {
+            return Err(WordError::InvalidFlagset("pc_up", pc_up));
+        }
+        if !matches!(ap_up, AP_Z2 | AP_ADD | AP_ONE) {
+            return Err(WordError::InvalidFlagset("ap_up", ap_up));
+        }
+        let immediate = if op1_base == Base::Pc {
+            let imm = program
+                .get(pos + 1)
+                .ok_or(WordError::MissingImmediate(pos))?;
+            Some(imm.word())
+        } else {
+            None
+        };
+        decoded.push(DecodedInstruction {
+            opcode,
+            dst: Address {
+                base: reg(instr.dst_reg()),
+                off: instr.off_dst_i16(),
+            },
+            op0: Address {
+                base: reg(instr.op0_reg()),
+                off: instr.off_op0_i16(),
+            },
+            op1: Address {
+                base: op1_base,
+                off: instr.off_op1_i16(),
+            },
+            res_log,
+            pc_up,
+            ap_up,
+            immediate,
+        });
+        pos += if immediate.is_some() { 2 } else { 1 };
+    }
+    Ok(decoded)
+}
 
-    /// Returns bit-flag for frame pointer for second register as `F`
-    fn f_op1_fp(&self) -> F;
+/// Splits the words of a program into its instructions, each paired with its position in the program,
+/// skipping the immediate values that follow the instructions using them.
+/// An instruction missing its immediate value at the end of the program is still returned.
+pub fn decode_words<F: Field>(words: &[F]) -> Vec<(usize, CairoWord<F>)> {
+    let mut instrs = vec![];
+    let mut pc = 0;
+    while pc < words.len() {
+        let instr = CairoWord::new(words[pc]);
+        instrs.push((pc, instr));
+        pc += if instr.uses_immediate() { 2 } else { 1 };
+    }
+    instrs
+}
 
-    /// Returns bit-flag for allocation pointer for second regsiter as `F`
-    fn f_op1_ap(&self) -> F;
+/// Packs the 16 bit-flags of an instruction into the integer whose i-th bit is the i-th bit-flag,
+/// which is the inverse of reading them with [Decomposition::flag_at]
+pub fn flags_to_u16(flags: &[bool; NUM_FLAGS]) -> u16 {
+    flags
+        .iter()
+        .rev()
+        .fold(0, |mask, flag| mask << 1 | u16::from(*flag))
+}
 
-    /// Returns bit-flag for addition operation in right side as `F`
-    fn f_res_add(&self) -> F;
+/// Returns the `pos`-th offset of 16 bits of an instruction as a signed integer in the interval `[-2^15,2^15)`
+fn signed_offset<F: Field>(word: F, pos: usize) -> i16 {
+    (word.chunk_u16(pos).to_u64() as i32 - (1 << 15)) as i16
+}
 
-    /// Returns bit-flag for multiplication operation in right side as `F`
-    fn f_res_mul(&self) -> F;
+/// Returns an offset in the interval `[-2^15,2^15)` to the unsigned 16 bits that encode it in an instruction.
+/// This is the inverse of [bias] on field elements, and in debug builds asserts that the offset lies in that interval.
+pub fn unbias<F: Field>(offset: F) -> F {
+    let unbiased = offset + F::from(2u16.pow(15u32));
+    debug_assert!(
+        unbiased.to_bits().iter().skip(16).all(|bit| !*bit),
+        "offset out of the interval [-2^15,2^15)"
+    );
+    unbiased
+}
 
-    /// Returns bit-flag for program counter update being absolute jump as `F`
-    fn f_pc_abs(&self) -> F;
+/// Returns a signed offset in the interval `[-2^15,2^15)` to the unsigned 16 bits that encode it in an instruction,
+/// as a field element. This is the inverse of [bias].
+pub fn unbias_i32<F: Field>(offset: i32) -> Result<F, WordError> {
+    if (-(1 << 15)..(1 << 15)).contains(&offset) {
+        Ok(F::from((offset + (1 << 15)) as u16))
+    } else {
+        Err(WordError::OffsetOutOfRange(offset))
+    }
+}
 
-    /// Returns bit-flag for program counter update being relative jump as `F`
-    fn f_pc_rel(&self) -> F;
+impl<F: Field> CairoWord<F> {
+    /// Creates a [CairoWord] from a field element that must be a well-formed instruction: it fits in
+    /// 64 bits, every flagset takes a valid value and the 16th bit-flag is zero.
+    /// Immediate values are created with [CairoWord::new] instead.
+    pub fn try_from_instruction(word: F) -> Result<CairoWord<F>, WordError> {
+        let instr = CairoWord(word);
+        let bits = word.to_bits();
+        if bits.iter().skip(POS_FLAGS + NUM_FLAGS).any(|bit| *bit) {
+            return Err(WordError::NotInstruction);
+        }
+        if bits[POS_FLAGS + NUM_FLAGS - 1] {
+            return Err(WordError::InvalidFlagset("f15", 1));
+        }
+        let flagsets: [(&str, u8, &[u8]); 5] = [
+            (
+                "op1_src",
+                instr.op1_src(),
+                &[OP1_DBL, OP1_VAL, OP1_FP, OP1_AP],
+            ),
+            ("res_log", instr.res_log(), &[RES_ONE, RES_ADD, RES_MUL]),
+            ("pc_up", instr.pc_up(), &[PC_SIZ, PC_ABS, PC_REL, PC_JNZ]),
+            ("ap_up", instr.ap_up(), &[AP_Z2, AP_ADD, AP_ONE]),
+            (
+                "opcode",
+                instr.opcode(),
+                &[OPC_JMP_INC, OPC_CALL, OPC_RET, OPC_AEQ],
+            ),
+        ];
+        for (name, flagset, valid) in flagsets {
+            if !valid.contains(&flagset) {
+                return Err(WordError::InvalidFlagset(name, flagset));
+            }
+        }
+        Ok(instr)
+    }
 
-    /// Returns bit-flag for program counter update being conditional jump as `F`
-    fn f_pc_jnz(&self) -> F;
+    /// Builds an instruction from its signed offsets and its 16 bit-flags, the inverse of [Decomposition]:
+    /// the offsets are biased by `2^15` into the three least significant chunks of 16 bits,
+    /// and the bit-flags fill the most significant chunk, the first of them being the least significant bit
+    pub fn assemble(
+        off_dst: i16,
+        off_op0: i16,
+        off_op1: i16,
+        flags: &[bool; NUM_FLAGS],
+    ) -> CairoWord<F> {
+        let unbias = |off: i16| (i32::from(off) + (1 << 15)) as u64;
+        let word = unbias(off_dst) << (16 * POS_DST)
+            | unbias(off_op0) << (16 * POS_OP0)
+            | unbias(off_op1) << (16 * POS_OP1)
+            | u64::from(flags_to_u16(flags)) << POS_FLAGS;
+        CairoWord(F::from(word))
+    }
 
-    /// Returns bit-flag for allocation counter update being a manual addition as `F`
-    fn f_ap_add(&self) -> F;
+    /// Returns the destination offset in biased representation, failing if the word does not fit
+    /// in 64 bits, as then it is an immediate value whose high bits would be decoded as garbage
+    pub fn checked_off_dst(&self) -> Result<F, WordError> {
+        self.check_instruction().map(|_| self.off_dst())
+    }
 
-    /// Returns bit-flag for allocation counter update being a self increment as `F`
-    fn f_ap_one(&self) -> F;
+    /// Returns the first operand offset in biased representation, failing like [CairoWord::checked_off_dst]
+    pub fn checked_off_op0(&self) -> Result<F, WordError> {
+        self.check_instruction().map(|_| self.off_op0())
+    }
 
-    /// Returns bit-flag for operation being a call as `F`
-    fn f_opc_call(&self) -> F;
+    /// Returns the second operand offset in biased representation, failing like [CairoWord::checked_off_dst]
+    pub fn checked_off_op1(&self) -> Result<F, WordError> {
+        self.check_instruction().map(|_| self.off_op1())
+    }
 
-    /// Returns bit-flag for operation being a return as `F`
-    fn f_opc_ret(&self) -> F;
+    /// Checks that the word fits in the 64 bits of an instruction
+    fn check_instruction(&self) -> Result<(), WordError> {
+        if self.word().to_bits().iter().skip(64).any(|bit| *bit) {
+            Err(WordError::NotInstruction)
+        } else {
+            Ok(())
+        }
+    }
 
-    /// Returns bit-flag for operation being an assert-equal as `F`
-    fn f_opc_aeq(&self) -> F;
+    /// Returns the destination offset as a signed integer in the interval `[-2^15,2^15)`
+    pub fn off_dst_i16(&self) -> i16 {
+        signed_offset(self.word(), POS_DST)
+    }
 
-    /// Returns bit-flag for 16th position
-    fn f15(&self) -> F;
+    /// Returns the first operand offset as a signed integer in the interval `[-2^15,2^15)`
+    pub fn off_op0_i16(&self) -> i16 {
+        signed_offset(self.word(), POS_OP0)
+    }
 
-    /// Returns flagset for destination register
-    fn dst_reg(&self) -> u8;
+    /// Returns the second operand offset as a signed integer in the interval `[-2^15,2^15)`
+    pub fn off_op1_i16(&self) -> i16 {
+        signed_offset(self.word(), POS_OP1)
+    }
 
-    /// Returns flagset for first operand register
-    fn op0_reg(&self) -> u8;
+    /// Returns the decoded fields in which this instruction differs from `other`: first the offsets
+    /// and the 16th bit-flag, then the flagsets. An empty vector means both words decode the same.
+    pub fn diff(&self, other: &Self) -> Vec<FieldDiff<F>> {
+        let mut diffs = vec![];
+        let elements: [(F, F, fn(F, F) -> FieldDiff<F>); 4] = [
+            (self.off_dst(), other.off_dst(), FieldDiff::OffDst),
+            (self.off_op0(), other.off_op0(), FieldDiff::OffOp0),
+            (self.off_op1(), other.off_op1(), FieldDiff::OffOp1),
+            (self.f15(), other.f15(), FieldDiff::F15),
+        ];
+        for (left, right, field) in elements {
+            if left != right {
+                diffs.push(field(left, right));
+            }
+        }
+        let flagsets: [(u8, u8, fn(u8, u8) -> FieldDiff<F>); 7] = [
+            (self.dst_reg(), other.dst_reg(), FieldDiff::DstReg),
+            (self.op0_reg(), other.op0_reg(), FieldDiff::Op0Reg),
+            (self.op1_src(), other.op1_src(), FieldDiff::Op1Src),
+            (self.res_log(), other.res_log(), FieldDiff::ResLog),
+            (self.pc_up(), other.pc_up(), FieldDiff::PcUp),
+            (self.ap_up(), other.ap_up(), FieldDiff::ApUp),
+            (self.opcode(), other.opcode(), FieldDiff::Opcode),
+        ];
+        for (left, right, field) in flagsets {
+            if left != right {
+                diffs.push(field(left, right));
+            }
+        }
+        diffs
+    }
 
-    /// Returns flagset for second operand register
-    fn op1_src(&self) -> u8;
+    /// Returns the value the destination of an assert-equal instruction holds after it is executed:
+    /// the result `res` is written in `dst` if it was unset, and otherwise both must coincide
+    pub fn assert_eq_effect(&self, dst_current: Option<F>, res: F) -> Result<F, RunError<F>> {
+        if self.opcode() != OPC_AEQ {
+            return Err(RunError::NotAssertEq);
+        }
+        match dst_current {
+            None => Ok(res),
+            Some(dst) if dst == res => Ok(dst),
+            Some(dst) => Err(RunError::AssertEqMismatch { dst, res }),
+        }
+    }
 
-    /// Returns flagset for result logics
-    fn res_log(&self) -> u8;
+    /// Returns the addresses of the destination, first operand and second operand of the instruction
+    /// for the registers `ap` and `fp`. The address of the second operand is `None` when it is an
+    /// immediate value, as well as for double indexing, where it depends on the content of the first operand.
+    pub fn addresses(&self, ap: F, fp: F) -> (F, F, Option<F>) {
+        let reg = |flag: u8| if flag == 0 { ap } else { fp };
+        let dst = reg(self.dst_reg()) + self.off_dst();
+        let op0 = reg(self.op0_reg()) + self.off_op0();
+        let op1 = match self.op1_src() {
+            OP1_FP => Some(fp + self.off_op1()),
+            OP1_AP => Some(ap + self.off_op1()),
+            _ => None,
+        };
+        (dst, op0, op1)
+    }
 
-    /// Returns flagset for program counter update
-    fn pc_up(&self) -> u8;
+    /// Returns the result of the instruction for the values of its operands: their sum, their product,
+    /// or the second operand alone, as given by the result logic.
+    /// Panics if the result logic is not a valid flagset.
+    pub fn compute_res(&self, op0: F, op1: F) -> F {
+        match self.res_log() {
+            RES_ONE => op1,
+            RES_ADD => op0 + op1,
+            RES_MUL => op0 * op1,
+            _ => panic!("Invalid res_log flagset"),
+        }
+    }
 
-    /// Returns flagset for allocation pointer update
-    fn ap_up(&self) -> u8;
+    /// Returns the program counter following the instruction at `pc` of `size` words, for the result
+    /// `res` and destination `dst` of the instruction: the next instruction, the absolute or relative
+    /// jump to `res`, or for a conditional jump the relative jump to `res` unless `dst` is zero.
+    /// As conditional jumps leave their result unused, they expect their second operand as `res`.
+    /// Panics if the program counter update is not a valid flagset.
+    pub fn next_pc(&self, pc: F, size: F, res: F, dst: F) -> F {
+        match self.pc_up() {
+            PC_SIZ => pc + size,
+            PC_ABS => res,
+            PC_REL => pc + res,
+            PC_JNZ if dst.is_zero() => pc + size,
+            PC_JNZ => pc + res,
+            _ => panic!("Invalid pc_up flagset"),
+        }
+    }
 
-    /// Returns flagset for operation code
-    fn opcode(&self) -> u8;
-}
+    /// Returns the allocation and frame pointers following the instruction, for their values `ap` and `fp`
+    /// and the result `res`, destination `dst` and first operand `op0` of the instruction, as computed by
+    /// [CairoWord::next_ap_fp]. The first operand, which a call sets to its return address, does not
+    /// affect the registers.
+    pub fn update_registers(&self, ap: F, fp: F, res: F, dst: F, _op0: F) -> (F, F) {
+        self.next_ap_fp(ap, fp, res, dst)
+    }
 
-impl<F: Field> Decomposition<F> for CairoWord<F> {
-    fn off_dst(&self) -> F {
-        // The least significant 16 bits
-        bias(self.word().chunk_u16(POS_DST))
+    /// Returns the allocation and frame pointers following the instruction, for their values `ap` and `fp`
+    /// and the result `res` and destination `dst` of the instruction. A call pushes two words and starts
+    /// the next frame after them, while a return restores the frame pointer saved at `dst`.
+    /// Panics if the allocation pointer update or the operation code is not a valid flagset,
+    /// or if a call updates the allocation pointer.
+    pub fn next_ap_fp(&self, ap: F, fp: F, res: F, dst: F) -> (F, F) {
+        if self.opcode() == OPC_CALL {
+            match self.ap_up() {
+                AP_Z2 => return (ap + F::from(2u32), ap + F::from(2u32)),
+                _ => panic!("ap increment in call instruction"),
+            }
+        }
+        let next_ap = match self.ap_up() {
+            AP_Z2 => ap,
+            AP_ADD => ap + res,
+            AP_ONE => ap + F::one(),
+            _ => panic!("Invalid ap_up flagset"),
+        };
+        let next_fp = match self.opcode() {
+            OPC_JMP_INC | OPC_AEQ => fp,
+            OPC_RET => dst,
+            _ => panic!("Invalid opcode flagset"),
+        };
+        (next_ap, next_fp)
     }
 
-    fn off_op0(&self) -> F {
-        // From the 32nd bit to the 17th
-        bias(self.word().chunk_u16(POS_OP0))
+    /// Returns whether the second operand of the instruction is an immediate value, which is the word
+    /// following the instruction in the program, so that the instruction takes two words
+    pub fn uses_immediate(&self) -> bool {
+        self.op1_src() == OP1_VAL
     }
 
-    fn off_op1(&self) -> F {
-        // From the 48th bit to the 33rd
-        bias(self.word().chunk_u16(POS_OP1))
+    /// Returns the kind of the instruction, as computed by [CairoWord::try_kind].
+    /// Panics when more than one operation code bit-flag is set.
+    pub fn kind(&self) -> InstructionKind {
+        self.try_kind().unwrap_or_else(|e| panic!("{}", e))
     }
 
-    fn flags(&self) -> Vec<F> {
-        let mut flags = Vec::with_capacity(NUM_FLAGS);
-        // The most significant 16 bits
-        for i in 0..NUM_FLAGS {
-            flags.push(self.flag_at(i));
+    /// Returns the kind of the instruction, failing when more than one operation code bit-flag is set
+    pub fn try_kind(&self) -> Result<InstructionKind, WordError> {
+        match self.opcode() {
+            OPC_JMP_INC => Ok(InstructionKind::Nop),
+            OPC_CALL => Ok(InstructionKind::Call),
+            OPC_RET => Ok(InstructionKind::Ret),
+            OPC_AEQ => Ok(InstructionKind::AssertEq),
+            other => Err(WordError::InvalidFlagset("opcode", other)),
         }
-        flags
     }
 
-    fn flag_at(&self, pos: usize) -> F {
-        self.word().to_bits()[POS_FLAGS + pos].into()
+    /// Checks the mutual exclusivity of the bit-flags the runner relies on when executing the instruction:
+    /// at most one bit-flag of the program counter update, of the allocation pointer update and of
+    /// the operation code is set, and the 16th bit-flag is zero
+    pub fn is_wellformed_flags(&self) -> bool {
+        matches!(self.pc_up(), PC_SIZ | PC_ABS | PC_REL | PC_JNZ)
+            && matches!(self.ap_up(), AP_Z2 | AP_ADD | AP_ONE)
+            && matches!(self.opcode(), OPC_JMP_INC | OPC_CALL | OPC_RET | OPC_AEQ)
+            && self.f15() == F::zero()
     }
 
-    fn f_dst_fp(&self) -> F {
-        self.flag_at(0)
+    /// Cheaply checks whether the word could be an instruction, to tell instructions from
+    /// immediate values when scanning memory without decoding: the word must fit in 63 bits,
+    /// as the 16th bit-flag is zero, and its operation code flagset must be a valid one.
+    /// Small immediate values cannot be told apart, as they look like instructions without flags.
+    pub fn looks_like_instruction(&self) -> bool {
+        self.word()
+            .to_bits()
+            .iter()
+            .skip(POS_FLAGS + NUM_FLAGS - 1)
+            .all(|bit| !bit)
+            && matches!(self.opcode(), OPC_JMP_INC | OPC_CALL | OPC_RET | OPC_AEQ)
     }
 
-    fn f_op0_fp(&self) -> F {
-        self.flag_at(1)
+    /// Returns the instruction in a human-readable form, such as `assert_eq [ap + 0], imm; ap++`
+    /// for `tempvar x = val`. The offsets are written as signed integers, and the operands read
+    /// from the immediate value following the instruction are written as `imm`.
+    pub fn disassemble(&self) -> String {
+        if let Err(e) = Self::try_from_instruction(self.word()) {
+            return format!("invalid instruction: {}", e);
+        }
+        let mem = |reg: &str, off: i16| {
+            let sign = if off < 0 { '-' } else { '+' };
+            format!("[{} {} {}]", reg, sign, i32::from(off).abs())
+        };
+        let reg = |flag: u8| if flag == 0 { "ap" } else { "fp" };
+        let dst = mem(reg(self.dst_reg()), self.off_dst_i16());
+        let op0 = mem(reg(self.op0_reg()), self.off_op0_i16());
+        let off_op1 = self.off_op1_i16();
+        let op1 = match self.op1_src() {
+            OP1_VAL if off_op1 == 1 => "imm".to_string(),
+            OP1_VAL => mem("pc", off_op1),
+            OP1_FP => mem("fp", off_op1),
+            OP1_AP => mem("ap", off_op1),
+            _ => mem(&op0, off_op1),
+        };
+        let res = match self.res_log() {
+            RES_ADD => format!("{} + {}", op0, op1),
+            RES_MUL => format!("{} * {}", op0, op1),
+            _ => op1.clone(),
+        };
+        let mut text = match (self.opcode(), self.pc_up()) {
+            (OPC_AEQ, _) => format!("assert_eq {}, {}", dst, res),
+            (OPC_CALL, PC_ABS) => format!("call abs {}", res),
+            (OPC_CALL, _) => format!("call rel {}", res),
+            (OPC_RET, _) => "ret".to_string(),
+            (_, PC_ABS) => format!("jmp abs {}", res),
+            (_, PC_REL) => format!("jmp rel {}", res),
+            (_, PC_JNZ) => format!("jmp rel {} if {} != 0", op1, dst),
+            // neither jumps nor writes, so that only ap is updated
+            _ => String::new(),
+        };
+        let ap_up = match self.ap_up() {
+            AP_ADD => format!("ap += {}", res),
+            AP_ONE => "ap++".to_string(),
+            _ => String::new(),
+        };
+        if !text.is_empty() && !ap_up.is_empty() {
+            text.push_str("; ");
+        }
+        text.push_str(&ap_up);
+        text
     }
 
-    fn f_op1_val(&self) -> F {
-        self.flag_at(2)
+    /// Returns the instruction as a JSON object with its signed offsets, its named bit-flags and
+    /// the name of its operation code, such as
+    /// `{"off_dst": -2, "off_op0": -1, "off_op1": -1, "flags": {"dst_fp": true, ...}, "opcode": "ret"}`
+    pub fn to_json(&self) -> serde_json::Value {
+        let flags: serde_json::Map<_, _> = FLAG_NAMES
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.to_string(), (self.flag_at(i) == F::one()).into()))
+            .collect();
+        serde_json::json!({
+            "off_dst": self.off_dst_i16(),
+            "off_op0": self.off_op0_i16(),
+            "off_op1": self.off_op1_i16(),
+            "flags": flags,
+            "opcode": opcode_name(self.opcode()),
+        })
     }
 
-    fn f_op1_fp(&self) -> F {
-        self.flag_at(3)
+    /// Builds an instruction from the JSON form of [CairoWord::to_json]. The operation code
+    /// is optional, but when present it must agree with the bit-flags.
+    pub fn from_json(json: &serde_json::Value) -> Result<CairoWord<F>, WordError> {
+        let offset = |name: &'static str| -> Result<u64, WordError> {
+            let offset = json[name]
+                .as_i64()
+                .and_then(|off| i32::try_from(off).ok())
+                .ok_or(WordError::InvalidJson(name))?;
+            Ok(unbias_i32::<F>(offset)?.to_u64())
+        };
+        let mut word = offset("off_dst")? | offset("off_op0")? << 16 | offset("off_op1")? << 32;
+        for (i, name) in FLAG_NAMES.into_iter().enumerate() {
+            let flag = json["flags"][name]
+                .as_bool()
+                .ok_or(WordError::InvalidJson(name))?;
+            word |= (flag as u64) << (POS_FLAGS + i);
+        }
+        let instr = CairoWord::new(F::from(word));
+        match json.get("opcode") {
+            None => Ok(instr),
+            Some(opcode) if opcode.as_str() == Some(opcode_name(instr.opcode())) => Ok(instr),
+            Some(_) => Err(WordError::InvalidJson("opcode")),
+        }
     }
 
-    fn f_op1_ap(&self) -> F {
-        self.flag_at(4)
+    /// Returns the serialization of the word, as laid out by [FieldHelpers::to_bytes]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
     }
 
-    fn f_res_add(&self) -> F {
-        self.flag_at(5)
+    /// Builds a word from its serialization by [CairoWord::to_bytes], failing when the bytes
+    /// are truncated, trailed by extra bytes or not the encoding of a field element
+    pub fn from_bytes(bytes: &[u8]) -> Result<CairoWord<F>, WordError> {
+        let word = F::from_bytes(bytes).map_err(|_| WordError::InvalidBytes)?;
+        if word.to_bytes().len() != bytes.len() {
+            return Err(WordError::InvalidBytes);
+        }
+        Ok(CairoWord::new(word))
     }
 
-    fn f_res_mul(&self) -> F {
-        self.flag_at(6)
+    /// Checks that an immediate value fits in `max_bits` bits, for backends that restrict immediates
+    /// to a smaller range than the full field. Negative values are seen as their field representative,
+    /// so they only fit in the full width of the field.
+    pub fn validate_immediate(value: F, max_bits: u32) -> Result<(), WordError> {
+        let bits = value
+            .to_bits()
+            .iter()
+            .rposition(|bit| *bit)
+            .map_or(0, |pos| pos as u32 + 1);
+        if bits > max_bits {
+            Err(WordError::ImmediateTooWide { bits, max_bits })
+        } else {
+            Ok(())
+        }
     }
 
-    fn f_pc_abs(&self) -> F {
-        self.flag_at(7)
+    /// Checks that the result logic is allowed for the operation code of the instruction.
+    /// Calls and returns jump to the single operand `res = op1`, whereas assert-equal and
+    /// jumps or increments admit any result logic, except for conditional jumps, whose
+    /// result is unused and must be `res = op1`.
+    pub fn validate_res_opcode(&self) -> Result<(), WordError> {
+        let (opcode, res_log) = (self.opcode(), self.res_log());
+        let valid = match opcode {
+            OPC_CALL | OPC_RET => res_log == RES_ONE,
+            _ if self.pc_up() == PC_JNZ => res_log == RES_ONE,
+            OPC_JMP_INC | OPC_AEQ => matches!(res_log, RES_ONE | RES_ADD | RES_MUL),
+            _ => false,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(WordError::InvalidResOpcode { opcode, res_log })
+        }
     }
 
-    fn f_pc_rel(&self) -> F {
-        self.flag_at(8)
+    /// Checks the rules tying the updates of the registers to the operation code, on top of
+    /// [CairoWord::validate_res_opcode]: calls jump to an absolute or relative address and
+    /// returns to an absolute one, neither of them updating `ap` through its flags, while
+    /// assert-equal cannot be a conditional jump, whose result is left unused. Conditional
+    /// jumps are plain jumps that do not add their unused result to `ap`.
+    pub fn check_opcode_invariants(&self) -> Result<(), WordError> {
+        self.validate_res_opcode()?;
+        let (opcode, pc_up, ap_up) = (self.opcode(), self.pc_up(), self.ap_up());
+        let invalid = |flagset, value| WordError::InvalidOpcodeFlagset {
+            opcode,
+            flagset,
+            value,
+        };
+        let pc_valid = match opcode {
+            OPC_CALL => matches!(pc_up, PC_ABS | PC_REL),
+            OPC_RET => pc_up == PC_ABS,
+            OPC_AEQ => pc_up != PC_JNZ,
+            _ => true,
+        };
+        if !pc_valid {
+            return Err(invalid("pc_up", pc_up));
+        }
+        if matches!(opcode, OPC_CALL | OPC_RET) && ap_up != AP_Z2 {
+            return Err(invalid("ap_up", ap_up));
+        }
+        if pc_up == PC_JNZ && ap_up == AP_ADD {
+            return Err(invalid("ap_up", ap_up));
+        }
+        Ok(())
     }
+}
 
-    fn f_pc_jnz(&self) -> F {
-        self.flag_at(9)
+/// Relocates a program compiled to start at address 0 so that it can be loaded at address `base`.
+/// Absolute jumps and calls whose target is given by an immediate value get their target shifted by `base`,
+/// while the remaining words are left untouched.
+pub fn relocate_program<F: Field>(
+    program: &[CairoWord<F>],
+    base: F,
+) -> Result<Vec<CairoWord<F>>, WordError> {
+    let mut relocated = program.to_vec();
+    let mut pos = 0;
+    while pos < program.len() {
+        let instr = program[pos];
+        if !instr.uses_immediate() {
+            pos += 1;
+            continue;
+        }
+        // the immediate value is the word following the instruction
+        let imm = program
+            .get(pos + 1)
+            .ok_or(WordError::MissingImmediate(pos))?;
+        if instr.pc_up() == PC_ABS && instr.res_log() == RES_ONE {
+            // the next pc is res = op1, that is the immediate value
+            relocated[pos + 1] = CairoWord::new(imm.word() + base);
+        }
+        pos += 2;
     }
+    Ok(relocated)
+}
 
-    fn f_ap_add(&self) -> F {
-        self.flag_at(10)
+/// Checks that the jumps and calls of a program compiled to start at address 0 whose target
+/// is given by an immediate value land on the beginning of an instruction, and not on an
+/// immediate value or outside of the program. Returns the positions of the offending instructions.
+pub fn validate_jump_targets<F: Field>(program: &[CairoWord<F>]) -> Result<(), Vec<usize>> {
+    let mut starts = HashSet::new();
+    let mut targets = vec![];
+    let mut pos = 0;
+    while pos < program.len() {
+        let instr = program[pos];
+        starts.insert(F::from(pos as u64));
+        if !instr.uses_immediate() {
+            pos += 1;
+            continue;
+        }
+        if let Some(imm) = program.get(pos + 1) {
+            let pc = F::from(pos as u64);
+            match (instr.pc_up(), instr.res_log()) {
+                (PC_ABS, RES_ONE) => targets.push((pos, imm.word())),
+                (PC_REL, RES_ONE) | (PC_JNZ, _) => targets.push((pos, pc + imm.word())),
+                _ => (),
+            }
+        }
+        pos += 2;
     }
+    let invalid: Vec<usize> = targets
+        .into_iter()
+        .filter(|(_, target)| !starts.contains(target))
+        .map(|(pos, _)| pos)
+        .collect();
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        Err(invalid)
+    }
+}
 
-    fn f_ap_one(&self) -> F {
-        self.flag_at(11)
+/// Checks that a sequence of instructions, along with their immediate values, is a basic block:
+/// only its last instruction may transfer control, by a jump, a call or a return
+pub fn is_basic_block<F: Field>(words: &[CairoWord<F>]) -> bool {
+    let mut pos = 0;
+    while pos < words.len() {
+        let instr = words[pos];
+        let size = if instr.uses_immediate() { 2 } else { 1 };
+        let transfers = instr.pc_up() != PC_SIZ || matches!(instr.opcode(), OPC_CALL | OPC_RET);
+        if transfers && pos + size < words.len() {
+            return false;
+        }
+        pos += size;
     }
+    true
+}
+
+/// Positions of the components of an instruction within a word. [CairoWord] decodes the standard layout
+/// of Cairo, with three offsets of 16 bits followed by the 16 bit-flags, but experimental encodings, such as
+/// wider offsets admitted by the larger fields of our curves, can be decoded with [WordLayout::decode].
+/// Every component must lie within the first 128 bits of the word.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WordLayout {
+    /// Number of bits of each offset
+    pub offset_bits: usize,
+    /// Position of the destination offset, counted in offsets
+    pub pos_dst: usize,
+    /// Position of the first operand offset, counted in offsets
+    pub pos_op0: usize,
+    /// Position of the second operand offset, counted in offsets
+    pub pos_op1: usize,
+    /// Bit position of the first of the 16 bit-flags
+    pub pos_flags: usize,
+}
 
-    fn f_opc_call(&self) -> F {
-        self.flag_at(12)
+impl Default for WordLayout {
+    fn default() -> Self {
+        WordLayout::STANDARD
     }
+}
 
-    fn f_opc_ret(&self) -> F {
-        self.flag_at(13)
+impl WordLayout {
+    /// The layout of Cairo instructions
+    pub const STANDARD: WordLayout = WordLayout {
+        offset_bits: 16,
+        pos_dst: POS_DST,
+        pos_op0: POS_OP0,
+        pos_op1: POS_OP1,
+        pos_flags: POS_FLAGS,
+    };
+
+    /// Returns the destination offset of a word in biased representation
+    pub fn off_dst<F: Field>(&self, word: F) -> F {
+        self.offset(word, self.pos_dst)
     }
 
-    fn f_opc_aeq(&self) -> F {
-        self.flag_at(14)
+    /// Returns the first operand offset of a word in biased representation
+    pub fn off_op0<F: Field>(&self, word: F) -> F {
+        self.offset(word, self.pos_op0)
     }
 
-    fn f15(&self) -> F {
-        self.flag_at(15)
+    /// Returns the second operand offset of a word in biased representation
+    pub fn off_op1<F: Field>(&self, word: F) -> F {
+        self.offset(word, self.pos_op1)
     }
 
-    fn dst_reg(&self) -> u8 {
-        // dst_reg = fDST_REG
-        self.f_dst_fp().least_significant_byte()
+    /// Returns the bit-flags of a word as an integer whose i-th bit is the i-th bit-flag
+    pub fn flag_bitmask<F: Field>(&self, word: F) -> u16 {
+        (low_u128(word) >> self.pos_flags) as u16
     }
 
-    fn op0_reg(&self) -> u8 {
-        // op0_reg = fOP0_REG
-        self.f_op0_fp().least_significant_byte()
+    /// Returns the `pos`-th offset of a word in biased representation,
+    /// which lies in the interval `[-2^(offset_bits-1),2^(offset_bits-1))`
+    fn offset<F: Field>(&self, word: F, pos: usize) -> F {
+        let chunk = (low_u128(word) >> (pos * self.offset_bits)) & ((1 << self.offset_bits) - 1);
+        F::from(chunk) - F::from(1u128 << (self.offset_bits - 1))
     }
 
-    fn op1_src(&self) -> u8 {
-        // op1_src = 4*fOP1_AP + 2*fOP1_FP + fOP1_VAL
-        2 * (2 * self.f_op1_ap().least_significant_byte()
-            + self.f_op1_fp().least_significant_byte())
-            + self.f_op1_val().least_significant_byte()
+    /// Decodes a word under this layout
+    pub fn decode<F: Field>(&self, word: F) -> LaidOutWord<F> {
+        LaidOutWord {
+            word,
+            layout: *self,
+        }
     }
+}
 
-    fn res_log(&self) -> u8 {
-        // res_log = 2*fRES_MUL + fRES_ADD
-        2 * self.f_res_mul().least_significant_byte() + self.f_res_add().least_significant_byte()
+/// A word decoded under a [WordLayout]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LaidOutWord<F> {
+    word: F,
+    layout: WordLayout,
+}
+
+impl<F: Field> Decomposition<F> for LaidOutWord<F> {
+    fn off_dst(&self) -> F {
+        self.layout.off_dst(self.word)
     }
 
-    fn pc_up(&self) -> u8 {
-        // pc_up = 4*fPC_JNZ + 2*fPC_REL + fPC_ABS
-        2 * (2 * self.f_pc_jnz().least_significant_byte()
-            + self.f_pc_rel().least_significant_byte())
-            + self.f_pc_abs().least_significant_byte()
+    fn off_op0(&self) -> F {
+        self.layout.off_op0(self.word)
     }
 
-    fn ap_up(&self) -> u8 {
-        // ap_up = 2*fAP_ONE + fAP_ADD
-        2 * self.f_ap_one().least_significant_byte() + self.f_ap_add().least_significant_byte()
+    fn off_op1(&self) -> F {
+        self.layout.off_op1(self.word)
     }
 
-    fn opcode(&self) -> u8 {
-        // opcode = 4*fOPC_AEQ + 2*fOPC_RET + fOPC_CALL
-        2 * (2 * self.f_opc_aeq().least_significant_byte()
-            + self.f_opc_ret().least_significant_byte())
-            + self.f_opc_call().least_significant_byte()
+    fn flag_bitmask(&self) -> u16 {
+        self.layout.flag_bitmask(self.word)
     }
 }
 
+/// Returns the first 128 bits of a field element, where bits beyond the size of the field are zero
+fn low_u128<F: Field>(word: F) -> u128 {
+    word.to_bytes()
+        .iter()
+        .take(16)
+        .rev()
+        .fold(0, |acc, byte| acc << 8 | u128::from(*byte))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::flags::*;
-    use crate::word::Decomposition;
+    use crate::runner::RunError;
+    use crate::word::{
+        decode_program, decode_words, flags_to_u16, is_basic_block, unbias, unbias_i32,
+        validate_jump_targets, Address, Base, DecodedInstruction, Decomposition, FieldDiff,
+        InstructionKind, Opcode, WordError,
+    };
     use ark_ff::{One, Zero};
     use mina_curves::pasta::fp::Fp as F;
+    use std::collections::HashMap;
+
+    /// A 63-bit prime field with modulus 2^63 - 25, barely large enough for Cairo instructions
+    mod small {
+        use ark_ff::{biginteger::BigInteger64 as BigInteger, FftParameters, Fp64, Fp64Parameters};
+
+        pub type Fs = Fp64<FsParameters>;
+
+        pub struct FsParameters;
+
+        impl Fp64Parameters for FsParameters {}
+        impl FftParameters for FsParameters {
+            type BigInt = BigInteger;
+
+            const TWO_ADICITY: u32 = 1;
+
+            const TWO_ADIC_ROOT_OF_UNITY: BigInteger = BigInteger([0x7fffffffffffffb5]);
+        }
+
+        impl ark_ff::FpParameters for FsParameters {
+            const MODULUS: BigInteger = BigInteger([0x7fffffffffffffe7]);
+
+            const R: BigInteger = BigInteger([0x32]);
+
+            const R2: BigInteger = BigInteger([0x9c4]);
+
+            const MODULUS_MINUS_ONE_DIV_TWO: BigInteger = BigInteger([0x3ffffffffffffff3]);
+
+            // T and T_MINUS_ONE_DIV_TWO, where MODULUS - 1 = 2^S * T
+            const T: BigInteger = BigInteger([0x3ffffffffffffff3]);
+
+            const T_MINUS_ONE_DIV_TWO: BigInteger = BigInteger([0x1ffffffffffffff9]);
+
+            // GENERATOR = 3
+            const GENERATOR: BigInteger = BigInteger([0x96]);
+
+            const MODULUS_BITS: u32 = 63;
+
+            const CAPACITY: u32 = Self::MODULUS_BITS - 1;
+
+            const REPR_SHAVE_BITS: u32 = 1;
+
+            // -(MODULUS^{-1} mod 2^64) mod 2^64
+            const INV: u64 = 1106804644422573097;
+        }
+    }
 
     #[test]
     fn test_biased() {
@@ -266,6 +982,25 @@ mod tests {
         assert_eq!(-F::one(), super::bias(F::from(0x7fff)));
     }
 
+    #[test]
+    fn test_unbiased() {
+        assert_eq!(F::from(0x8001), unbias(F::one()));
+        assert_eq!(F::from(0x8000), unbias(F::zero()));
+        assert_eq!(F::from(0x7fff), unbias(-F::one()));
+        assert_eq!(F::zero(), unbias(-F::from(0x8000)));
+        assert_eq!(F::from(0xffff), unbias(F::from(0x7fff)));
+        for x in 0..(1u32 << 16) {
+            assert_eq!(F::from(x), unbias(super::bias(F::from(x))));
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "out of the interval")]
+    fn test_unbiased_out_of_range() {
+        unbias(F::from(0x8000));
+    }
+
     #[test]
     fn test_cairo_word() {
         // Tests the structure of a Cairo word corresponding to the Cairo instruction: tempvar x = val
@@ -293,6 +1028,11 @@ mod tests {
         assert_eq!(word.f_opc_aeq(), F::one());
         assert_eq!(word.f15(), F::zero());
 
+        assert_eq!(word.flags(), word.flags_array().to_vec());
+        for (i, flag) in word.flags_array().iter().enumerate() {
+            assert_eq!(*flag, word.flag_at(i));
+        }
+
         assert_eq!(word.dst_reg(), DST_AP);
         assert_eq!(word.op0_reg(), 1 - OP0_AP);
         assert_eq!(word.op1_src(), OP1_VAL);
@@ -311,5 +1051,800 @@ mod tests {
                 + 2u32.pow(10) * u32::from(word.ap_up())
                 + 2u32.pow(12) * u32::from(word.opcode())
         );
+        assert_eq!(word.flagsets().pack(), 0x4806);
+    }
+
+    #[test]
+    fn test_try_from_instruction() {
+        let word = F::from(0x480680017fff8000u64);
+        assert_eq!(
+            super::CairoWord::try_from_instruction(word),
+            Ok(super::CairoWord::new(word))
+        );
+        // the same instruction with the 16th bit-flag set
+        assert_eq!(
+            super::CairoWord::try_from_instruction(F::from(0xc80680017fff8000u64)),
+            Err(WordError::InvalidFlagset("f15", 1))
+        );
+        // both relative and conditional jumps
+        assert_eq!(
+            super::CairoWord::try_from_instruction(F::from(0x4b0680017fff8000u64)),
+            Err(WordError::InvalidFlagset("pc_up", PC_REL + PC_JNZ))
+        );
+        // both an addition and a multiplication
+        assert_eq!(
+            super::CairoWord::try_from_instruction(F::from(0x486680017fff8000u64)),
+            Err(WordError::InvalidFlagset("res_log", RES_ADD + RES_MUL))
+        );
+        // words above 64 bits are immediate values
+        assert_eq!(
+            super::CairoWord::try_from_instruction(F::from(u64::MAX) + F::one()),
+            Err(WordError::NotInstruction)
+        );
+        assert_eq!(
+            super::CairoWord::try_from_instruction(
+                F::from(u64::MAX) + F::one() + F::from(0x480680017fff8000u64)
+            ),
+            Err(WordError::NotInstruction)
+        );
+    }
+
+    #[test]
+    fn test_assemble() {
+        let word = super::CairoWord::new(F::from(0x480680017fff8000u64));
+        let flags = word.flags_array().map(|flag| flag == F::one());
+        let assembled = super::CairoWord::assemble(0, -1, 1, &flags);
+        assert_eq!(assembled, word);
+        assert_eq!(assembled.flag_bitmask(), 0x4806);
+
+        // round trip through the decomposition of the instructions of a program
+        for instr in [
+            0x208b7fff7fff7ffeu64,
+            0x1104800180018000,
+            0x400380007ffc7ffd,
+        ] {
+            let word = super::CairoWord::<F>::new(F::from(instr));
+            let flags = word.flags_array().map(|flag| flag == F::one());
+            assert_eq!(
+                super::CairoWord::assemble(
+                    word.off_dst_i16(),
+                    word.off_op0_i16(),
+                    word.off_op1_i16(),
+                    &flags
+                ),
+                word
+            );
+        }
+        assert_eq!(
+            super::CairoWord::<F>::assemble(i16::MIN, i16::MAX, 0, &[false; NUM_FLAGS]).word(),
+            F::from(0x8000ffff0000u64)
+        );
+    }
+
+    #[test]
+    fn test_disassemble() {
+        // tempvar x = val, decoded as OPC_AEQ, RES_ONE, PC_SIZ and AP_ONE in test_cairo_word
+        let word = super::CairoWord::new(F::from(0x480680017fff8000u64));
+        assert_eq!(word.disassemble(), "assert_eq [ap + 0], imm; ap++");
+
+        let disassemble = |instr: u64| super::CairoWord::new(F::from(instr)).disassemble();
+        assert_eq!(disassemble(0x208b7fff7fff7ffe), "ret");
+        assert_eq!(disassemble(0x1104800180018000), "call rel imm");
+        assert_eq!(
+            disassemble(0x48307fff7fff8000),
+            "assert_eq [ap + 0], [ap - 1] + [ap - 1]; ap++"
+        );
+        assert_eq!(
+            disassemble(0x400380007ffc7ffd),
+            "assert_eq [fp - 3], [[fp - 4] + 0]"
+        );
+        assert_eq!(
+            disassemble(0xc80680017fff8000),
+            "invalid instruction: invalid f15 flagset 1"
+        );
+    }
+
+    #[test]
+    fn test_offsets_i16() {
+        let word = super::CairoWord::new(F::from(0x480680017fff8000u64));
+        assert_eq!(word.off_dst_i16(), 0);
+        assert_eq!(word.off_op0_i16(), -1);
+        assert_eq!(word.off_op1_i16(), 1);
+
+        // the signed offsets agree with the biased field elements
+        let ret = super::CairoWord::new(F::from(0x208b7fff7fff7ffeu64));
+        for word in [word, ret] {
+            let offsets = [
+                (word.off_dst_i16(), word.off_dst()),
+                (word.off_op0_i16(), word.off_op0()),
+                (word.off_op1_i16(), word.off_op1()),
+            ];
+            for (off, biased) in offsets {
+                assert_eq!(unbias_i32::<F>(off.into()).map(super::bias), Ok(biased));
+            }
+        }
+        assert_eq!(ret.off_dst_i16(), -2);
+    }
+
+    #[test]
+    fn test_wellformed_flags() {
+        let wellformed = |instr: u64| super::CairoWord::new(F::from(instr)).is_wellformed_flags();
+        // tempvar x = val, call rel and ret
+        assert!(wellformed(0x480680017fff8000));
+        assert!(wellformed(0x1104800180018000));
+        assert!(wellformed(0x208b7fff7fff7ffe));
+
+        // the call with both f_pc_rel and f_pc_jnz
+        assert!(!wellformed(0x1304800180018000));
+        // the tempvar with both f_ap_add and f_ap_one
+        assert!(!wellformed(0x4c0680017fff8000));
+        // the tempvar with both f_opc_ret and f_opc_aeq
+        assert!(!wellformed(0x680680017fff8000));
+        // the tempvar with f15 set
+        assert!(!wellformed(0xc80680017fff8000));
+    }
+
+    #[test]
+    fn test_compute_res() {
+        // tempvar x = 10
+        let tempvar = super::CairoWord::new(F::from(0x480680017fff8000u64));
+        let (op0, op1) = (F::from(3u32), F::from(10u32));
+        assert_eq!(tempvar.compute_res(op0, op1), op1);
+        assert_ne!(tempvar.compute_res(op0, op1), op0 * op1);
+        assert_ne!(tempvar.compute_res(op0, op1), op0 + op1);
+
+        // the same word with f_res_add, then f_res_mul
+        let add = super::CairoWord::new(F::from(0x480680017fff8000u64 | 1 << 53));
+        assert_eq!(add.compute_res(op0, op1), F::from(13u32));
+        let mul = super::CairoWord::new(F::from(0x480680017fff8000u64 | 1 << 54));
+        assert_eq!(mul.compute_res(op0, op1), F::from(30u32));
+    }
+
+    #[test]
+    fn test_next_pc() {
+        // tempvar x = 10, then the same word with f_pc_abs, f_pc_rel and f_pc_jnz
+        let word = |flag: u64| super::CairoWord::new(F::from(0x480680017fff8000u64 | flag));
+        let (pc, size, res) = (F::from(5u32), F::from(2u32), F::from(20u32));
+        let dst = F::from(7u32);
+        assert_eq!(word(0).next_pc(pc, size, res, dst), F::from(7u32));
+        assert_eq!(word(1 << 55).next_pc(pc, size, res, dst), F::from(20u32));
+        assert_eq!(word(1 << 56).next_pc(pc, size, res, dst), F::from(25u32));
+
+        // jnz jumps unless the destination is zero
+        let jnz = word(1 << 57);
+        assert_eq!(jnz.next_pc(pc, size, res, dst), F::from(25u32));
+        assert_eq!(jnz.next_pc(pc, size, res, F::zero()), F::from(7u32));
+    }
+
+    #[test]
+    fn test_update_registers() {
+        let (ap, fp) = (F::from(10u32), F::from(4u32));
+        let (res, dst, op0) = (F::from(3u32), F::from(2u32), F::from(7u32));
+
+        // tempvar x = 10 increments ap and keeps fp
+        let tempvar = super::CairoWord::new(F::from(0x480680017fff8000u64));
+        assert_eq!(
+            tempvar.update_registers(ap, fp, res, dst, op0),
+            (F::from(11u32), fp)
+        );
+        // call rel 2 pushes fp and the return address, and starts the next frame after them
+        let call = super::CairoWord::new(F::from(0x1104800180018000u64));
+        assert_eq!(
+            call.update_registers(ap, fp, res, dst, op0),
+            (F::from(12u32), F::from(12u32))
+        );
+        // return() restores the previous frame
+        let ret = super::CairoWord::new(F::from(0x208b7fff7fff7ffeu64));
+        assert_eq!(ret.update_registers(ap, fp, res, dst, op0), (ap, dst));
+        // ap += 3
+        let ap_add = super::CairoWord::new(F::from(0x040780017fff7fffu64));
+        assert_eq!(
+            ap_add.update_registers(ap, fp, res, dst, op0),
+            (F::from(13u32), fp)
+        );
+        assert_eq!(
+            ap_add.next_ap_fp(ap, fp, res, dst),
+            ap_add.update_registers(ap, fp, res, dst, op0)
+        );
+    }
+
+    #[test]
+    fn test_opcode_invariants() {
+        let check = |instr: u64| super::CairoWord::new(F::from(instr)).check_opcode_invariants();
+        // call rel imm, return(), tempvar x = 10 and jmp rel imm if [ap - 1] != 0
+        assert_eq!(check(0x1104800180018000), Ok(()));
+        assert_eq!(check(0x208b7fff7fff7ffe), Ok(()));
+        assert_eq!(check(0x480680017fff8000), Ok(()));
+        assert_eq!(check(0x020680017fff7fff), Ok(()));
+
+        // the call with f_pc_jnz set instead of f_pc_rel
+        assert_eq!(
+            check(0x1204800180018000),
+            Err(WordError::InvalidOpcodeFlagset {
+                opcode: OPC_CALL,
+                flagset: "pc_up",
+                value: PC_JNZ,
+            })
+        );
+        // the call with f_ap_one
+        assert_eq!(
+            check(0x1904800180018000),
+            Err(WordError::InvalidOpcodeFlagset {
+                opcode: OPC_CALL,
+                flagset: "ap_up",
+                value: AP_ONE,
+            })
+        );
+        // the tempvar with f_pc_jnz
+        assert_eq!(
+            check(0x4a0680017fff8000),
+            Err(WordError::InvalidOpcodeFlagset {
+                opcode: OPC_AEQ,
+                flagset: "pc_up",
+                value: PC_JNZ,
+            })
+        );
+    }
+
+    #[test]
+    fn test_uses_immediate() {
+        let tempvar = super::CairoWord::new(F::from(0x480680017fff8000u64));
+        assert!(tempvar.uses_immediate());
+        let ret = super::CairoWord::new(F::from(0x208b7fff7fff7ffeu64));
+        assert!(!ret.uses_immediate());
+    }
+
+    #[test]
+    fn test_instruction_kind() {
+        let kind = |instr: u64| super::CairoWord::new(F::from(instr)).kind();
+        assert_eq!(kind(0x480680017fff8000), InstructionKind::AssertEq);
+        assert_eq!(kind(0x1104800180018000), InstructionKind::Call);
+        assert_eq!(kind(0x208b7fff7fff7ffe), InstructionKind::Ret);
+        // jmp rel imm
+        assert_eq!(kind(0x010780017fff7fff), InstructionKind::Nop);
+        // the call with f_opc_ret also set
+        let call_ret = super::CairoWord::new(F::from(0x3104800180018000u64));
+        assert_eq!(
+            call_ret.try_kind(),
+            Err(WordError::InvalidFlagset("opcode", OPC_CALL + OPC_RET))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid opcode flagset")]
+    fn test_instruction_kind_invalid() {
+        super::CairoWord::new(F::from(0x3104800180018000u64)).kind();
+    }
+
+    #[test]
+    fn test_word_layout() {
+        let word = F::from(0x480680017fff8000u64);
+        let standard = super::WordLayout::default().decode(word);
+        let cairo = super::CairoWord::new(word);
+        assert_eq!(standard.off_dst(), cairo.off_dst());
+        assert_eq!(standard.off_op0(), cairo.off_op0());
+        assert_eq!(standard.off_op1(), cairo.off_op1());
+        assert_eq!(standard.flags_array(), cairo.flags_array());
+        assert_eq!(standard.opcode(), OPC_AEQ);
+
+        // the same instruction with offsets of 20 bits
+        let wide = super::WordLayout {
+            offset_bits: 20,
+            pos_flags: 60,
+            ..super::WordLayout::STANDARD
+        };
+        let word = F::from(0x4806u128 << 60 | 0x80001u128 << 40 | 0x7ffffu128 << 20 | 0x80000u128);
+        let decoded = wide.decode(word);
+        assert_eq!(decoded.off_dst(), F::zero());
+        assert_eq!(decoded.off_op0(), -F::one());
+        assert_eq!(decoded.off_op1(), F::one());
+        assert_eq!(decoded.flag_bitmask(), cairo.flag_bitmask());
+        assert_eq!(decoded.pc_up(), PC_SIZ);
+        assert_eq!(decoded.ap_up(), AP_ONE);
+    }
+
+    #[test]
+    fn test_checked_offsets() {
+        let word = super::CairoWord::new(F::from(0x480680017fff8000u64));
+        assert_eq!(word.checked_off_dst(), Ok(F::zero()));
+        assert_eq!(word.checked_off_op0(), Ok(-F::one()));
+        assert_eq!(word.checked_off_op1(), Ok(F::one()));
+
+        // an immediate value of the full width of the field
+        let imm = super::CairoWord::new(-F::one());
+        assert_eq!(imm.checked_off_dst(), Err(WordError::NotInstruction));
+        assert_eq!(imm.checked_off_op0(), Err(WordError::NotInstruction));
+        assert_eq!(imm.checked_off_op1(), Err(WordError::NotInstruction));
+        assert_eq!(WordError::NotInstruction.to_string(), "not an instruction");
+    }
+
+    #[test]
+    fn test_flags_to_u16() {
+        let word = super::CairoWord::new(F::from(0x480680017fff8000u64));
+        let flags = word.flags_array().map(|flag| flag == F::one());
+        assert_eq!(flags_to_u16(&flags), 0x4806);
+        for (i, flag) in flags.iter().enumerate() {
+            assert_eq!(word.flag_at(i) == F::one(), *flag);
+        }
+
+        // f_op0_fp, f_op1_val, f_ap_one and f_opc_aeq
+        let mut set = [false; NUM_FLAGS];
+        for i in [1, 2, 11, 14] {
+            set[i] = true;
+        }
+        assert_eq!(flags_to_u16(&set), word.flag_bitmask());
+        assert_eq!(
+            u64::from(flags_to_u16(&set)) << POS_FLAGS,
+            0x480680017fff8000u64 & (0xffff << POS_FLAGS)
+        );
+    }
+
+    #[test]
+    fn test_addresses() {
+        let (ap, fp) = (F::from(10u32), F::from(20u32));
+        // tempvar x = val writes [ap] from the immediate value
+        let word = super::CairoWord::new(F::from(0x480680017fff8000u64));
+        assert_eq!(
+            word.addresses(ap, fp),
+            (F::from(10u32), F::from(19u32), None)
+        );
+        // [ap] = [ap - 1] + [ap - 1]
+        let word = super::CairoWord::new(F::from(0x48307fff7fff8000u64));
+        assert_eq!(
+            word.addresses(ap, fp),
+            (F::from(10u32), F::from(9u32), Some(F::from(9u32)))
+        );
+        // ret reads [fp - 2] and [fp - 1]
+        let word = super::CairoWord::new(F::from(0x208b7fff7fff7ffeu64));
+        assert_eq!(
+            word.addresses(ap, fp),
+            (F::from(18u32), F::from(19u32), Some(F::from(19u32)))
+        );
+    }
+
+    #[test]
+    fn test_word_as_key() {
+        // Counts the distinct instructions among some words of `test_cairo_output` in runner.rs
+        let instrs = vec![
+            0x48307fff7fff8000u64,
+            0x480a7ffd7fff8000,
+            0x48127ffb7fff8000,
+            0x1104800180018000,
+            0x48127ff87fff8000,
+            0x1104800180018000,
+            0x48127ff67fff8000,
+            0x1104800180018000,
+        ];
+        let mut count = HashMap::new();
+        for instr in instrs {
+            *count
+                .entry(super::CairoWord::new(F::from(instr)))
+                .or_insert(0) += 1;
+        }
+        assert_eq!(count.len(), 6);
+        assert_eq!(
+            count[&super::CairoWord::new(F::from(0x1104800180018000u64))],
+            3
+        );
+        assert_eq!(
+            super::CairoWord::new(F::from(0x480a7ffd7fff8000u64)),
+            super::CairoWord::new(F::from(0x480a7ffd7fff8000u64))
+        );
+    }
+
+    #[test]
+    fn test_res_opcode() {
+        // tempvar x = val
+        let aeq = super::CairoWord::new(F::from(0x480680017fff8000u64));
+        assert_eq!(aeq.validate_res_opcode(), Ok(()));
+        // call rel
+        let call = super::CairoWord::new(F::from(0x1104800180018000u64));
+        assert_eq!(call.validate_res_opcode(), Ok(()));
+        // ret, with the flag of an addition in the result
+        let ret = super::CairoWord::new(F::from(0x20ab7fff7fff7ffeu64));
+        assert_eq!(
+            ret.validate_res_opcode(),
+            Err(WordError::InvalidResOpcode {
+                opcode: OPC_RET,
+                res_log: RES_ADD
+            })
+        );
+        // jmp rel imm if [ap - 1] != 0
+        let jnz = super::CairoWord::new(F::from(0x020680017fff7fffu64));
+        assert_eq!(jnz.pc_up(), PC_JNZ);
+        assert_eq!(jnz.validate_res_opcode(), Ok(()));
+        // the same conditional jump, with the flag of an addition in its unused result
+        let jnz_add = super::CairoWord::new(F::from(0x022680017fff7fffu64));
+        let err = Err(WordError::InvalidResOpcode {
+            opcode: OPC_JMP_INC,
+            res_log: RES_ADD,
+        });
+        assert_eq!(jnz_add.validate_res_opcode(), err);
+        assert_eq!(jnz_add.check_opcode_invariants(), err);
+    }
+
+    #[test]
+    fn test_diff() {
+        // [ap + 0] = [ap - 1] + imm versus [ap + 0] = [ap - 2] + imm
+        let word = super::CairoWord::new(F::from(0x480680017fff8000u64));
+        let other = super::CairoWord::new(F::from(0x480680017ffe8000u64));
+        assert_eq!(word.diff(&word), vec![]);
+        assert_eq!(
+            word.diff(&other),
+            vec![FieldDiff::OffOp0(-F::one(), -F::from(2u32))]
+        );
+    }
+
+    #[test]
+    fn test_decode_program() {
+        // tempvar x = 10
+        // return()
+        let program: Vec<_> = [0x480680017fff8000u64, 10, 0x208b7fff7fff7ffe]
+            .iter()
+            .map(|w| super::CairoWord::new(F::from(*w)))
+            .collect();
+        let decoded = decode_program(&program).unwrap();
+        assert_eq!(
+            decoded,
+            vec![
+                DecodedInstruction {
+                    opcode: Opcode::AssertEq,
+                    dst: Address {
+                        base: Base::Ap,
+                        off: 0
+                    },
+                    op0: Address {
+                        base: Base::Ap,
+                        off: -1
+                    },
+                    op1: Address {
+                        base: Base::Pc,
+                        off: 1
+                    },
+                    res_log: RES_ONE,
+                    pc_up: PC_SIZ,
+                    ap_up: AP_ONE,
+                    immediate: Some(F::from(10u32)),
+                },
+                DecodedInstruction {
+                    opcode: Opcode::Ret,
+                    dst: Address {
+                        base: Base::Fp,
+                        off: -2
+                    },
+                    op0: Address {
+                        base: Base::Fp,
+                        off: -1
+                    },
+                    op1: Address {
+                        base: Base::Fp,
+                        off: -1
+                    },
+                    res_log: RES_ONE,
+                    pc_up: PC_ABS,
+                    ap_up: AP_Z2,
+                    immediate: None,
+                },
+            ]
+        );
+
+        // the immediate of the tempvar is missing
+        assert_eq!(
+            decode_program(&program[..1]),
+            Err(WordError::MissingImmediate(0))
+        );
+        // both pc_rel and pc_jnz flags are set
+        let jump = super::CairoWord::new(F::from(0x03007fff7fff7fffu64));
+        assert_eq!(
+            decode_program(&[jump]),
+            Err(WordError::InvalidFlagset("pc_up", PC_REL | PC_JNZ))
+        );
+    }
+
+    #[test]
+    fn test_small_field() {
+        use small::Fs;
+
+        // the sample instruction decodes the same as in the Pallas field
+        let word = super::CairoWord::new(Fs::from(0x480680017fff8000u64));
+        assert_eq!(word.off_dst(), Fs::zero());
+        assert_eq!(word.off_op0(), -Fs::one());
+        assert_eq!(word.off_op1(), Fs::one());
+        assert_eq!(word.op1_src(), OP1_VAL);
+        assert_eq!(word.ap_up(), AP_ONE);
+        assert_eq!(word.opcode(), OPC_AEQ);
+
+        // every flag but the 16th is set, and the offsets lie on the boundaries of their range
+        let word = super::CairoWord::new(Fs::from(0x7fff000080007fffu64));
+        assert_eq!(word.off_dst(), -Fs::one());
+        assert_eq!(word.off_op0(), Fs::zero());
+        assert_eq!(word.off_op1(), -Fs::from(1u64 << 15));
+        for i in 0..NUM_FLAGS - 1 {
+            assert_eq!(word.flag_at(i), Fs::one());
+        }
+        assert_eq!(word.f15(), Fs::zero());
+        assert_eq!(word.flags_array().to_vec(), word.flags());
+        assert_eq!(word.op1_src(), 7);
+        assert_eq!(word.res_log(), 3);
+        assert_eq!(word.pc_up(), 7);
+        assert_eq!(word.ap_up(), 3);
+        assert_eq!(word.opcode(), 7);
+    }
+
+    #[test]
+    fn test_jump_targets() {
+        // jmp rel 2
+        // tempvar x = 10
+        // return()
+        let mut program: Vec<_> = [
+            0x010780017fff7fffu64,
+            2,
+            0x480680017fff8000,
+            10,
+            0x208b7fff7fff7ffe,
+        ]
+        .iter()
+        .map(|w| super::CairoWord::new(F::from(*w)))
+        .collect();
+        assert_eq!(validate_jump_targets(&program), Ok(()));
+
+        // jmp rel 3 lands on the immediate of the tempvar
+        program[1] = super::CairoWord::new(F::from(3u64));
+        assert_eq!(validate_jump_targets(&program), Err(vec![0]));
+    }
+
+    #[test]
+    fn test_basic_block() {
+        let words = |ws: &[u64]| -> Vec<_> {
+            ws.iter()
+                .map(|w| super::CairoWord::new(F::from(*w)))
+                .collect()
+        };
+        // tempvar x = 10
+        // [ap] = [ap-1] + 1; ap++
+        // return()
+        let block = words(&[
+            0x480680017fff8000,
+            10,
+            0x482480017fff8000,
+            1,
+            0x208b7fff7fff7ffe,
+        ]);
+        assert!(is_basic_block(&block));
+        // without the return, and with the immediate of the last instruction
+        assert!(is_basic_block(&block[..4]));
+        assert!(is_basic_block::<F>(&[]));
+
+        // tempvar x = 10
+        // jmp rel 2
+        // return()
+        let block = words(&[
+            0x480680017fff8000,
+            10,
+            0x010780017fff7fff,
+            2,
+            0x208b7fff7fff7ffe,
+        ]);
+        assert!(!is_basic_block(&block));
+        // the jump ends the block, immediate included
+        assert!(is_basic_block(&block[..4]));
+    }
+
+    #[test]
+    fn test_decode_words() {
+        // tempvar x = 10; return()
+        let words = [
+            F::from(0x480680017fff8000u64),
+            F::from(10u64),
+            F::from(0x208b7fff7fff7ffeu64),
+        ];
+        let instrs = decode_words(&words);
+        assert_eq!(
+            instrs,
+            vec![
+                (0, super::CairoWord::new(words[0])),
+                (2, super::CairoWord::new(words[2])),
+            ]
+        );
+        // the immediate is missing
+        assert_eq!(
+            decode_words(&words[..1]),
+            vec![(0, super::CairoWord::new(words[0]))]
+        );
+        assert!(decode_words::<F>(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_bytes() {
+        let instr = super::CairoWord::new(F::from(0x480680017fff8000u64));
+        let bytes = instr.to_bytes();
+        assert_eq!(super::CairoWord::from_bytes(&bytes), Ok(instr));
+        assert_eq!(
+            super::CairoWord::<F>::from_bytes(&bytes).map(|w| w.word()),
+            Ok(F::from(0x480680017fff8000u64))
+        );
+
+        assert_eq!(
+            super::CairoWord::<F>::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(WordError::InvalidBytes)
+        );
+        assert_eq!(
+            super::CairoWord::<F>::from_bytes(&[]),
+            Err(WordError::InvalidBytes)
+        );
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert_eq!(
+            super::CairoWord::<F>::from_bytes(&trailing),
+            Err(WordError::InvalidBytes)
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        // return()
+        let ret = super::CairoWord::new(F::from(0x208b7fff7fff7ffeu64));
+        let json = ret.to_json();
+        assert_eq!(json["off_dst"], -2);
+        assert_eq!(json["off_op1"], -1);
+        assert_eq!(json["flags"]["dst_fp"], true);
+        assert_eq!(json["flags"]["op1_val"], false);
+        assert_eq!(json["opcode"], "ret");
+        assert_eq!(super::CairoWord::from_json(&json), Ok(ret));
+
+        // the JSON can be written by hand, without an opcode
+        let tempvar: serde_json::Value = serde_json::from_str(
+            r#"{
+                "off_dst": 0, "off_op0": -1, "off_op1": 1,
+                "flags": {
+                    "dst_fp": false, "op0_fp": true, "op1_val": true, "op1_fp": false,
+                    "op1_ap": false, "res_add": false, "res_mul": false, "pc_abs": false,
+                    "pc_rel": false, "pc_jnz": false, "ap_add": false, "ap_one": true,
+                    "opc_call": false, "opc_ret": false, "opc_aeq": true
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            super::CairoWord::from_json(&tempvar),
+            Ok(super::CairoWord::new(F::from(0x480680017fff8000u64)))
+        );
+
+        let mut invalid = json.clone();
+        invalid["opcode"] = "call".into();
+        assert_eq!(
+            super::CairoWord::<F>::from_json(&invalid),
+            Err(WordError::InvalidJson("opcode"))
+        );
+        let mut invalid = json.clone();
+        invalid["off_op0"] = 40000.into();
+        assert_eq!(
+            super::CairoWord::<F>::from_json(&invalid),
+            Err(WordError::OffsetOutOfRange(40000))
+        );
+        let mut invalid = json;
+        invalid["flags"]["pc_abs"] = 1.into();
+        assert_eq!(
+            super::CairoWord::<F>::from_json(&invalid),
+            Err(WordError::InvalidJson("pc_abs"))
+        );
+    }
+
+    #[test]
+    fn test_flag_bitmask() {
+        for instr in [
+            0x480680017fff8000u64,
+            0x208b7fff7fff7ffe,
+            0x1104800180018000,
+            0x020680017fff7fff,
+            0x482480017fff8000,
+            0x48127ffb7fff8000,
+        ] {
+            let word = super::CairoWord::new(F::from(instr));
+            let mask = word.flag_bitmask();
+            assert_eq!(mask, (instr >> 48) as u16);
+            for i in 0..NUM_FLAGS {
+                assert_eq!(word.flag_at(i), F::from((mask >> i) & 1));
+            }
+            // each flagset weighs its bit-flags as documented
+            let bit = |i: usize| ((mask >> i) & 1) as u8;
+            assert_eq!(word.dst_reg(), bit(0));
+            assert_eq!(word.op0_reg(), bit(1));
+            assert_eq!(word.op1_src(), 4 * bit(4) + 2 * bit(3) + bit(2));
+            assert_eq!(word.res_log(), 2 * bit(6) + bit(5));
+            assert_eq!(word.pc_up(), 4 * bit(9) + 2 * bit(8) + bit(7));
+            assert_eq!(word.ap_up(), 2 * bit(11) + bit(10));
+            assert_eq!(word.opcode(), 4 * bit(14) + 2 * bit(13) + bit(12));
+        }
+    }
+
+    #[test]
+    fn test_looks_like_instruction() {
+        for instr in [
+            0x480680017fff8000u64,
+            0x208b7fff7fff7ffe,
+            0x1104800180018000,
+        ] {
+            assert!(super::CairoWord::new(F::from(instr)).looks_like_instruction());
+        }
+        // the 16th bit-flag is set
+        assert!(!super::CairoWord::new(F::from(0xc80680017fff8000u64)).looks_like_instruction());
+        // the operation code sets both the call and return bit-flags
+        assert!(!super::CairoWord::new(F::from(0x300680017fff8000u64)).looks_like_instruction());
+        // large immediates do not fit in 63 bits
+        assert!(!super::CairoWord::new(-F::one()).looks_like_instruction());
+        assert!(
+            !super::CairoWord::new(F::from(1u64 << 63) * F::from(1000u32)).looks_like_instruction()
+        );
+    }
+
+    #[test]
+    fn test_assert_eq_effect() {
+        // tempvar x = 10
+        let aeq = super::CairoWord::new(F::from(0x480680017fff8000u64));
+        let res = F::from(10u32);
+        // the unset destination gets written
+        assert_eq!(aeq.assert_eq_effect(None, res), Ok(res));
+        // the set destination is checked for consistency
+        assert_eq!(aeq.assert_eq_effect(Some(res), res), Ok(res));
+        assert_eq!(
+            aeq.assert_eq_effect(Some(F::from(11u32)), res),
+            Err(RunError::AssertEqMismatch {
+                dst: F::from(11u32),
+                res
+            })
+        );
+        // a return is not an assert-equal
+        let ret = super::CairoWord::new(F::from(0x208b7fff7fff7ffeu64));
+        assert_eq!(ret.assert_eq_effect(None, res), Err(RunError::NotAssertEq));
+    }
+
+    #[test]
+    fn test_validate_immediate() {
+        type W = super::CairoWord<F>;
+        assert_eq!(W::validate_immediate(F::zero(), 0), Ok(()));
+        assert_eq!(W::validate_immediate(F::from(255u32), 8), Ok(()));
+        assert_eq!(
+            W::validate_immediate(F::from(256u32), 8),
+            Err(WordError::ImmediateTooWide {
+                bits: 9,
+                max_bits: 8
+            })
+        );
+        assert_eq!(W::validate_immediate(F::from(256u32), 9), Ok(()));
+        assert_eq!(W::validate_immediate(-F::one(), 255), Ok(()));
+        assert_eq!(
+            W::validate_immediate(-F::one(), 64),
+            Err(WordError::ImmediateTooWide {
+                bits: 255,
+                max_bits: 64
+            })
+        );
+    }
+
+    #[test]
+    fn test_unbias_i32() {
+        assert_eq!(unbias_i32::<F>(0), Ok(F::from(0x8000u32)));
+        assert_eq!(unbias_i32::<F>(1), Ok(F::from(0x8001u32)));
+        assert_eq!(unbias_i32::<F>(-1), Ok(F::from(0x7fffu32)));
+        assert_eq!(unbias_i32::<F>(-32768), Ok(F::zero()));
+        assert_eq!(unbias_i32::<F>(32767), Ok(F::from(0xffffu32)));
+        assert_eq!(
+            unbias_i32::<F>(32768),
+            Err(WordError::OffsetOutOfRange(32768))
+        );
+        assert_eq!(
+            unbias_i32::<F>(-32769),
+            Err(WordError::OffsetOutOfRange(-32769))
+        );
+        // bias is the inverse
+        for (off, biased) in [
+            (-32768, -F::from(32768u32)),
+            (-1, -F::one()),
+            (0, F::zero()),
+            (32767, F::from(32767u32)),
+        ] {
+            assert_eq!(super::bias(unbias_i32::<F>(off).unwrap()), biased);
+        }
     }
 }
@@ -8,7 +8,7 @@ use crate::{alphas::Alphas, circuits::expr::prologue::*};
 use ark_ff::FftField;
 use serde::{Deserialize, Serialize};
 
-use super::gate::GateType;
+use super::gate::{CurrOrNext, GateType};
 
 /// A constraint type represents a polynomial that will be part of the final equation f (the circuit equation)
 #[derive(PartialEq, Eq, Clone, Copy, Hash, Debug, Serialize, Deserialize)]
@@ -23,6 +23,18 @@ pub enum ArgumentType {
     Lookup,
 }
 
+/// The rows a gate spans and the constraints it contributes, to lay out a circuit
+/// and estimate its constraints without evaluating them
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct GateFootprint {
+    /// The number of rows taken by the gate, including its companion rows
+    pub rows: usize,
+    /// The number of constraints of the gate
+    pub constraints: u32,
+    /// Whether the constraints read the row following the gate
+    pub next_row_used: bool,
+}
+
 /// The interface for a minimal argument implementation.
 pub trait Argument<F: FftField> {
     /// The type of constraints that this will produce.
@@ -37,6 +49,19 @@ pub trait Argument<F: FftField> {
     // TODO: return a [_; Self::CONSTRAINTS] once generic consts are stable
     fn constraints() -> Vec<E<F>>;
 
+    /// Returns the footprint of the argument as a gate, which spans a second row
+    /// when its constraints read the next one
+    fn gate_footprint() -> GateFootprint {
+        let next_row_used = Self::constraints()
+            .iter()
+            .any(|c| c.references_row(CurrOrNext::Next));
+        GateFootprint {
+            rows: if next_row_used { 2 } else { 1 },
+            constraints: Self::CONSTRAINTS,
+            next_row_used,
+        }
+    }
+
     /// Returns constraints safely combined via the passed combinator.
     fn combined_constraints(alphas: &Alphas<F>) -> E<F> {
         let constraints = Self::constraints();
@@ -484,6 +484,7 @@ impl<C> Expr<C> {
         Expr::Cell(Variable { col, row })
     }
 
+    /// Doubles the expression, which is cheaper to evaluate than adding it to a clone of itself
     pub fn double(self) -> Self {
         Expr::Double(Box::new(self))
     }
@@ -497,6 +498,28 @@ impl<C> Expr<C> {
         Expr::Constant(c)
     }
 
+    /// Returns whether a cell of the column `col`, on any row, appears in the expression
+    pub fn references_column(&self, col: Column) -> bool {
+        use Expr::*;
+        match self {
+            Constant(_) | VanishesOnLast4Rows | UnnormalizedLagrangeBasis(_) => false,
+            Cell(v) => v.col == col,
+            Double(x) | Square(x) | Pow(x, _) | Cache(_, x) => x.references_column(col),
+            BinOp(_, x, y) => x.references_column(col) || y.references_column(col),
+        }
+    }
+
+    /// Returns whether a cell of the row `row`, in any column, appears in the expression
+    pub fn references_row(&self, row: CurrOrNext) -> bool {
+        use Expr::*;
+        match self {
+            Constant(_) | VanishesOnLast4Rows | UnnormalizedLagrangeBasis(_) => false,
+            Cell(v) => v.row == row,
+            Double(x) | Square(x) | Pow(x, _) | Cache(_, x) => x.references_row(row),
+            BinOp(_, x, y) => x.references_row(row) || y.references_row(row),
+        }
+    }
+
     fn degree(&self, d1_size: u64) -> u64 {
         use Expr::*;
         match self {
@@ -2109,7 +2132,88 @@ pub fn coeff<F>(i: usize) -> E<F> {
     E::<F>::cell(Column::Coefficient(i), CurrOrNext::Curr)
 }
 
+/// Counts the multiplications and squarings of the expressions, which raise their degree.
+/// The ones of a cached subexpression are counted once, however many times it is shared.
+pub fn count_mul_gates<F>(exprs: &[E<F>]) -> usize {
+    fn count<F>(e: &E<F>, seen: &mut HashSet<CacheId>) -> usize {
+        match e {
+            Expr::Constant(_)
+            | Expr::Cell(_)
+            | Expr::VanishesOnLast4Rows
+            | Expr::UnnormalizedLagrangeBasis(_) => 0,
+            Expr::Double(x) => count(x, seen),
+            // square-and-multiply: a squaring for each bit after the leading one,
+            // and a multiplication for each other set bit
+            Expr::Pow(x, k) => {
+                let squarings = 63usize.saturating_sub(k.leading_zeros() as usize);
+                let muls = (k.count_ones() as usize).saturating_sub(1);
+                count(x, seen) + squarings + muls
+            }
+            Expr::Square(x) => 1 + count(x, seen),
+            Expr::BinOp(op, x, y) => {
+                usize::from(matches!(op, Op2::Mul)) + count(x, seen) + count(y, seen)
+            }
+            Expr::Cache(id, x) => {
+                if seen.insert(*id) {
+                    count(x, seen)
+                } else {
+                    0
+                }
+            }
+        }
+    }
+    let mut seen = HashSet::new();
+    exprs.iter().map(|e| count(e, &mut seen)).sum()
+}
+
 /// You can import this module like `use kimchi::circuits::expr::prologue::*` to obtain a number of handy aliases and helpers
 pub mod prologue {
     pub use super::{coeff, index, witness, witness_curr, witness_next, E};
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuits::wires::COLUMNS;
+    use array_init::array_init;
+    use mina_curves::pasta::fp::Fp as F;
+
+    #[test]
+    fn test_double() {
+        let domain = D::<F>::new(2).unwrap();
+        let evals = [
+            ProofEvaluations::dummy_with_witness_evaluations(array_init(|_| F::from(3u64))),
+            ProofEvaluations::dummy_with_witness_evaluations(array_init(|_| F::zero())),
+        ];
+        let constants = Constants {
+            alpha: F::zero(),
+            beta: F::zero(),
+            gamma: F::zero(),
+            joint_combiner: F::zero(),
+            endo_coefficient: F::zero(),
+            mds: vec![],
+        };
+        let eval = |e: E<F>| e.evaluate_(domain, domain.group_gen, &evals, &constants);
+
+        assert_eq!(eval(E::one().double()), Ok(F::from(2u64)));
+        assert_eq!(eval(witness_curr(COLUMNS - 1).double()), Ok(F::from(6u64)));
+    }
+
+    #[test]
+    fn test_count_mul_gates() {
+        let mut cache = Cache::default();
+        let x = witness_curr::<F>(0);
+        let squared = cache.cache(x.clone() * x.clone());
+        let exprs = vec![
+            squared.clone() * witness_curr(1) - x.clone().square(),
+            squared + x.double(),
+        ];
+        // the cached product is only counted the first time
+        assert_eq!(count_mul_gates(&exprs), 3);
+
+        // x^5 = (x^2)^2 * x and x^8 = ((x^2)^2)^2
+        assert_eq!(count_mul_gates(&[x.clone().pow(5)]), 3);
+        assert_eq!(count_mul_gates(&[x.clone().pow(8)]), 3);
+        assert_eq!(count_mul_gates(&[(x.clone() * x).pow(3)]), 3);
+    }
+}
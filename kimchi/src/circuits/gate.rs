@@ -139,7 +139,7 @@ impl<F: FftField> CircuitGate<F> {
             Generic => self.verify_generic(row, witness, public),
             Poseidon => self.verify_poseidon(row, witness, cs),
             CompleteAdd => self.verify_complete_add(row, witness),
-            VarBaseMul => self.verify_vbmul(row, witness),
+            VarBaseMul => self.verify_vbmul(row, witness).map_err(String::from),
             EndoMul => self.verify_endomul(row, witness, cs),
             EndoMulScalar => self.verify_endomul_scalar(row, witness, cs),
             // TODO: implement the verification for chacha
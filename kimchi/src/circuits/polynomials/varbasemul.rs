@@ -12,14 +12,20 @@
 
 use std::marker::PhantomData;
 
-use ark_ff::{FftField, One};
+use ark_ec::{short_weierstrass_jacobian::GroupAffine, SWModelParameters};
+use ark_ff::{FftField, Field, One};
+use ark_poly::{EvaluationDomain, Radix2EvaluationDomain as D};
+use array_init::array_init;
+use o1_utils::FieldHelpers;
+use thiserror::Error;
 use CurrOrNext::{Curr, Next};
 
 use crate::circuits::{
-    argument::{Argument, ArgumentType},
-    expr::{prologue::*, Cache, Column, Variable},
+    argument::{Argument, ArgumentType, GateFootprint},
+    expr::{prologue::*, Cache, Column, Constants, Variable},
     gate::{CircuitGate, CurrOrNext, GateType},
-    wires::{GateWires, COLUMNS},
+    scalars::ProofEvaluations,
+    wires::{GateWires, Wire, COLUMNS},
 };
 
 /// Implementation of short Weierstrass curve variable base scalar multiplication custom Plonk constraints.
@@ -132,9 +138,64 @@ impl<F: FftField> CircuitGate<F> {
         ]
     }
 
-    pub fn verify_vbmul(&self, _row: usize, _witness: &[Vec<F>; COLUMNS]) -> Result<(), String> {
-        // TODO: implement
-        Ok(())
+    /// Creates the gates of a VBSM chain of `num_chunks` chunks from `start_row`, each `VarBaseMul`
+    /// gate followed by its `Zero` companion, with the wiring of unconnected rows
+    pub fn create_vbmul_chain(start_row: usize, num_chunks: usize) -> Vec<Self> {
+        (0..num_chunks)
+            .flat_map(|i| {
+                let row = start_row + 2 * i;
+                Self::create_vbmul(&[Wire::new(row), Wire::new(row + 1)])
+            })
+            .collect()
+    }
+
+    /// Checks the 21 constraints of the gate on the VBSM row `row` of the witness and the row after it,
+    /// reporting the first constraint that does not hold.
+    /// With the `log_vbmul_residuals` feature, a failure also prints every nonzero residual to stderr.
+    pub fn verify_vbmul(
+        &self,
+        row: usize,
+        witness: &[Vec<F>; COLUMNS],
+    ) -> Result<(), VarbaseMulError> {
+        self.verify_vbmul_residuals(row, witness)
+            .map_err(|(e, residuals)| {
+                if cfg!(feature = "log_vbmul_residuals") {
+                    for (tag, residual) in residuals {
+                        eprintln!(
+                            "VarBaseMul row {}: {:?} has residual {}",
+                            row, tag, residual
+                        );
+                    }
+                }
+                e
+            })
+    }
+
+    /// Checks the gate like [CircuitGate::verify_vbmul], also returning on failure every constraint
+    /// that does not vanish on the rows, with its value, as computed by [VarbaseMul::residuals].
+    /// The residuals tell a slightly off witness from a wrong one.
+    pub fn verify_vbmul_residuals(
+        &self,
+        row: usize,
+        witness: &[Vec<F>; COLUMNS],
+    ) -> Result<(), (VarbaseMulError, Vec<(ConstraintTag, F)>)> {
+        let bits = LAYOUT.bits.map(|b| get(witness, row, b));
+        let n_prev = get(witness, row, LAYOUT.n_prev);
+        let n_next = get(witness, row, LAYOUT.n_next);
+        let n_holds = check_n_recomposition(n_prev, &bits, n_next);
+
+        let residuals = VarbaseMul::residuals(witness, row);
+        if !n_holds {
+            return Err((VarbaseMulError::NRecomposition, residuals));
+        }
+        let first = residuals
+            .iter()
+            .map(|(tag, _)| *tag)
+            .find(|tag| *tag != ConstraintTag::NRecomposition);
+        match first {
+            None => Ok(()),
+            Some(tag) => Err((tag.into(), residuals)),
+        }
     }
 
     pub fn vbmul(&self) -> F {
@@ -146,51 +207,246 @@ impl<F: FftField> CircuitGate<F> {
     }
 }
 
+/// Checks that `n_next` appends the 5 `bits` of a chunk, most significant first, to `n_prev`,
+/// that is `n_next = 32 * n_prev + 16 * b0 + 8 * b1 + 4 * b2 + 2 * b3 + b4`
+pub fn check_n_recomposition<F: Field>(n_prev: F, bits: &[F; 5], n_next: F) -> bool {
+    bits.iter().fold(n_prev, |n, b| n.double() + b) == n_next
+}
+
+/// Checks the VBSM chain whose gates start at `start_row`, made of the consecutive pairs of a `VarBaseMul`
+/// gate and its `Zero` companion up to the first row that holds another gate.
+/// On top of the constraints of every pair, checks that the `n` of each chunk carries the `n'` of the
+/// previous one, which the gate constrains through the wiring rather than within its rows.
+/// Returns the first failing row along with the reason.
+pub fn verify_vbmul_chain<F: FftField>(
+    gates: &[CircuitGate<F>],
+    witness: &[Vec<F>; COLUMNS],
+    start_row: usize,
+) -> Result<(), (usize, VarbaseMulError)> {
+    let is_vbmul = |row: usize| matches!(gates.get(row), Some(g) if g.typ == GateType::VarBaseMul);
+    if !is_vbmul(start_row) {
+        return Err((start_row, VarbaseMulError::NotVarBaseMul));
+    }
+    let mut row = start_row;
+    while is_vbmul(row) {
+        if !matches!(gates.get(row + 1), Some(g) if g.typ == GateType::Zero) {
+            return Err((row, VarbaseMulError::NotVarBaseMul));
+        }
+        if row > start_row
+            && get(witness, row, LAYOUT.n_prev) != get(witness, row - 2, LAYOUT.n_next)
+        {
+            return Err((row, VarbaseMulError::NCarry));
+        }
+        gates[row]
+            .verify_vbmul(row, witness)
+            .map_err(|e| (row, e))?;
+        row += 2;
+    }
+    Ok(())
+}
+
+/// The constraint of the VarBaseMul gate that a row of the witness violates
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VarbaseMulError {
+    #[error("boolean constraint of bit {bit} failed")]
+    BooleanBit { bit: usize },
+
+    #[error("slope constraint of bit {index} failed")]
+    SlopeConstraint { index: usize },
+
+    #[error("output x constraint of bit {index} failed")]
+    OutputX { index: usize },
+
+    #[error("output y constraint of bit {index} failed")]
+    OutputY { index: usize },
+
+    #[error("n recomposition failed")]
+    NRecomposition,
+
+    #[error("n does not continue the n' of the previous chunk")]
+    NCarry,
+
+    #[error("the rows do not hold a VarBaseMul gate followed by a Zero gate")]
+    NotVarBaseMul,
+}
+
+impl From<VarbaseMulError> for String {
+    fn from(e: VarbaseMulError) -> Self {
+        e.to_string()
+    }
+}
+
+impl From<ConstraintTag> for VarbaseMulError {
+    fn from(tag: ConstraintTag) -> Self {
+        match tag {
+            ConstraintTag::NRecomposition => VarbaseMulError::NRecomposition,
+            ConstraintTag::Boolean(bit) => VarbaseMulError::BooleanBit { bit },
+            ConstraintTag::S1Slope(index) => VarbaseMulError::SlopeConstraint { index },
+            ConstraintTag::OutputX(index) => VarbaseMulError::OutputX { index },
+            ConstraintTag::OutputY(index) => VarbaseMulError::OutputY { index },
+        }
+    }
+}
+
+/// Errors that can arise when filling the witness of a VBSM chain
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WitnessError {
+    #[error("can only set witness columns, not {0:?}")]
+    NotWitnessColumn(Column),
+
+    #[error("the number of bits {bits} is not a multiple of {chunk}")]
+    ChunkSize { bits: usize, chunk: usize },
+
+    #[error("the first row of the chain does not start from the expected n")]
+    UnexpectedStart,
+
+    #[error("fewer pairs of rows are given than the {chunks} chunks of bits")]
+    MissingRows { chunks: usize },
+
+    #[error("chunks of {chunk} bits do not fit in the {slots} bit slots of the layout")]
+    InvalidChunk { chunk: usize, slots: usize },
+
+    #[error("exceptional addition for bit {bit} of chunk {chunk}")]
+    ExceptionalAddition { chunk: usize, bit: usize },
+
+    #[error("a bit can only be 0 or 1")]
+    NonBooleanBit,
+}
+
+/// A field element that is known to be 0 or 1, as the bits written to the witness of a VBSM chain
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bit<F>(F);
+
+impl<F: FftField> Bit<F> {
+    /// The bit 0
+    pub fn zero() -> Self {
+        Bit(F::zero())
+    }
+
+    /// The bit 1
+    pub fn one() -> Self {
+        Bit(F::one())
+    }
+
+    /// The bit as a field element
+    pub fn value(self) -> F {
+        self.0
+    }
+}
+
+impl<F: FftField> TryFrom<F> for Bit<F> {
+    type Error = WitnessError;
+
+    /// Checks that the field element is a bit, to report it when filling the witness
+    /// rather than when proving with it
+    fn try_from(x: F) -> Result<Self, WitnessError> {
+        if x.is_zero() || x.is_one() {
+            Ok(Bit(x))
+        } else {
+            Err(WitnessError::NonBooleanBit)
+        }
+    }
+}
+
+impl<F: FftField> From<bool> for Bit<F> {
+    fn from(b: bool) -> Self {
+        if b {
+            Bit::one()
+        } else {
+            Bit::zero()
+        }
+    }
+}
+
 type CurveVar = (Variable, Variable);
 
-fn set<F>(w: &mut [Vec<F>; COLUMNS], row0: usize, var: Variable, x: F) {
+/// Sets a cell of the layout, where `rows` holds the rows of the `VarBaseMul` gate and of its
+/// `Zero` companion, standing for `Curr` and `Next`
+fn set<F>(
+    w: &mut [Vec<F>; COLUMNS],
+    rows: (usize, usize),
+    var: Variable,
+    x: F,
+) -> Result<(), WitnessError> {
+    let row = match var.row {
+        Curr => rows.0,
+        Next => rows.1,
+    };
     match var.col {
-        Column::Witness(i) => w[i][row0 + var.row.shift()] = x,
-        _ => panic!("Can only set witness columns"),
+        Column::Witness(i) => {
+            w[i][row] = x;
+            Ok(())
+        }
+        col => Err(WitnessError::NotWitnessColumn(col)),
     }
 }
 
+fn get<F: Copy>(w: &[Vec<F>; COLUMNS], row0: usize, var: Variable) -> F {
+    match var.col {
+        Column::Witness(i) => w[i][row0 + var.row.shift()],
+        _ => panic!("Can only get witness columns"),
+    }
+}
+
+/// Fills the cells of a bit, whose `position` in the chain is given as `(chunk, bit)`,
+/// failing on the exceptional cases where one of the slopes divides by zero.
+/// `q_y` is the y coordinate of the point `Q` added for the bit, that is `base.y` or `-base.y`.
 #[allow(clippy::too_many_arguments)]
 fn single_bit_witness<F: FftField>(
     w: &mut [Vec<F>; COLUMNS],
-    row: usize,
+    rows: (usize, usize),
+    position: (usize, usize),
     b: Variable,
     base: CurveVar,
     s1: Variable,
     input: CurveVar,
     output: CurveVar,
-    b_value: F,
+    b_value: Bit<F>,
     base_value: (F, F),
+    q_y: F,
     input_value: (F, F),
-) -> (F, F) {
-    let mut set = |var, x| set(w, row, var, x);
+) -> Result<(F, F), WitnessError> {
+    let mut set = |var, x| set(w, rows, var, x);
+
+    set(b, b_value.value())?;
+    set(input.0, input_value.0)?;
+    set(input.1, input_value.1)?;
 
-    set(b, b_value);
-    set(input.0, input_value.0);
-    set(input.1, input_value.1);
+    set(base.0, base_value.0)?;
+    set(base.1, base_value.1)?;
 
-    set(base.0, base_value.0);
-    set(base.1, base_value.1);
+    // The accumulator of each bit is the output of the previous one, so the inversions
+    // cannot be batched across the chain. Within a bit, however, the two denominators
+    //   s1 = num / den
+    //   s2 = 2*input.y / (2*input.x + base.x - s1^2) - s1
+    // can both be expressed over den, as
+    //   2*input.x + base.x - s1^2 = d / den^2
+    // with d = (2*input.x + base.x) * den^2 - num^2, so that a single inversion is needed.
+    let num = input_value.1 - q_y;
+    let den = input_value.0 - base_value.0;
+    let den_squared = den.square();
+    let d = (input_value.0.double() + base_value.0) * den_squared - num.square();
+    if den.is_zero() || d.is_zero() {
+        let (chunk, bit) = position;
+        return Err(WitnessError::ExceptionalAddition { chunk, bit });
+    }
+
+    let mut inverses = [den, d];
+    ark_ff::batch_inversion::<F>(&mut inverses);
+    let [den_inv, d_inv] = inverses;
 
-    let s1_value = (input_value.1 - (base_value.1 * (b_value.double() - F::one())))
-        / (input_value.0 - base_value.0);
+    let s1_value = num * den_inv;
 
-    set(s1, s1_value);
+    set(s1, s1_value)?;
 
     let s1_squared = s1_value.square();
 
-    let s2 =
-        input_value.1.double() / (input_value.0.double() + base_value.0 - s1_squared) - s1_value;
+    let s2 = input_value.1.double() * den_squared * d_inv - s1_value;
     let out_x = base_value.0 + s2.square() - s1_squared;
     let out_y = (input_value.0 - out_x) * s2 - input_value.1;
-    set(output.0, out_x);
-    set(output.1, out_y);
-    (out_x, out_y)
+    set(output.0, out_x)?;
+    set(output.1, out_y)?;
+    Ok((out_x, out_y))
 }
 
 fn single_bit<F: FftField>(
@@ -202,20 +458,19 @@ fn single_bit<F: FftField>(
     output: CurveVar,
 ) -> Vec<E<F>> {
     let v = E::Cell;
-    let double = |x: E<_>| x.clone() + x;
-
-    let b_sign = double(v(b)) - E::one();
 
-    let s1_squared = cache.cache(v(s1) * v(s1));
+    // doubling a cell refers to it once in the expression graph, rather than once per summand
+    let b_sign = v(b).double() - E::one();
 
     // s1 = (input.y - (2b - 1) * base.y) / (input.x - base.x)
     // s2 = 2*input.y / (2*input.x + base.x – s1^2) - s1
     // output.x = base.x + s2^2 - s1^2
     // output.y = (input.x – output.x) * s2 - input.y
 
-    let rx = s1_squared.clone() - v(input.0) - v(base.0);
+    // s1^2 is only read through t, so it is not cached on its own
+    let rx = v(s1) * v(s1) - v(input.0) - v(base.0);
     let t = cache.cache(v(input.0) - rx);
-    let u = cache.cache(double(v(input.1)) - t.clone() * v(s1));
+    let u = cache.cache(v(input.1).double() - t.clone() * v(s1));
     // s2 = u / t
 
     // output.x = base.x + s2^2 - s1^2
@@ -223,6 +478,7 @@ fn single_bit<F: FftField>(
     // output.x = base.x + u^2 / t^2 - s1^2
     // output.x - base.x + s1^2 =  u^2 / t^2
     // t^2 (output.x - base.x + s1^2) =  u^2
+    // t^2 (output.x + 2*input.x - t) =  u^2
     //
     // output.y = (input.x – output.x) * s2 - input.y
     // <=>
@@ -237,19 +493,84 @@ fn single_bit<F: FftField>(
         //   (input.x - base.x) * s1 = input.y – (2b-1)*base.y
         (v(input.0) - v(base.0)) * v(s1) - (v(input.1) - b_sign * v(base.1)),
         // constrain output.x
-        (u.clone() * u.clone()) - (t.clone() * t.clone()) * (v(output.0) - v(base.0) + s1_squared),
+        (u.clone() * u.clone())
+            - (t.clone() * t.clone()) * (v(output.0) + v(input.0).double() - t.clone()),
         // constrain output.y
         (v(output.1) + v(input.1)) * t - (v(input.0) - v(output.0)) * u,
     ]
 }
 
-struct Layout {
-    accs: [(Variable, Variable); 6],
-    bits: [Variable; 5],
-    ss: [Variable; 5],
-    base: (Variable, Variable),
-    n_prev: Variable,
-    n_next: Variable,
+/// The cells of the two rows of a `VarBaseMul` gate holding each value of a chunk of 5 bits,
+/// as laid out in the table below
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    /// The accumulators, from the input of the chunk to the output of its last bit
+    pub accs: [(Variable, Variable); 6],
+    /// The bits of the chunk, most significant first
+    pub bits: [Variable; 5],
+    /// The first slope of the addition of each bit
+    pub ss: [Variable; 5],
+    /// The base point
+    pub base: (Variable, Variable),
+    /// The scalar accumulated by the previous chunks
+    pub n_prev: Variable,
+    /// The scalar accumulated up to this chunk
+    pub n_next: Variable,
+}
+
+/// The row and witness column of a cell of the layout
+fn position(var: Variable) -> (CurrOrNext, usize) {
+    match var.col {
+        Column::Witness(i) => (var.row, i),
+        col => unreachable!("the layout only holds witness columns, not {:?}", col),
+    }
+}
+
+impl Layout {
+    /// The positions of the coordinates of the `i`-th accumulator
+    pub fn acc(&self, i: usize) -> ((CurrOrNext, usize), (CurrOrNext, usize)) {
+        (position(self.accs[i].0), position(self.accs[i].1))
+    }
+
+    /// The position of the `i`-th bit of the chunk, most significant first
+    pub fn bit(&self, i: usize) -> (CurrOrNext, usize) {
+        position(self.bits[i])
+    }
+
+    /// The position of the first slope of the `i`-th bit
+    pub fn s(&self, i: usize) -> (CurrOrNext, usize) {
+        position(self.ss[i])
+    }
+
+    /// The positions of the coordinates of the base
+    pub fn base(&self) -> ((CurrOrNext, usize), (CurrOrNext, usize)) {
+        (position(self.base.0), position(self.base.1))
+    }
+
+    /// The position of the scalar accumulated by the previous chunks
+    pub fn n_prev(&self) -> (CurrOrNext, usize) {
+        position(self.n_prev)
+    }
+
+    /// The position of the scalar accumulated up to this chunk
+    pub fn n_next(&self) -> (CurrOrNext, usize) {
+        position(self.n_next)
+    }
+
+    /// Returns every cell of the layout, across the two rows of the gate: the 6 accumulators,
+    /// the base, the 5 bits and their slopes, and the scalars `n` and `n'`
+    pub fn all_variables(&self) -> Vec<Variable> {
+        let mut vars = vec![];
+        for (x, y) in self.accs.iter().chain([&self.base]) {
+            vars.push(*x);
+            vars.push(*y);
+        }
+        vars.extend(self.bits);
+        vars.extend(self.ss);
+        vars.push(self.n_prev);
+        vars.push(self.n_next);
+        vars
+    }
 }
 
 // We lay things out like
@@ -281,35 +602,383 @@ const LAYOUT: Layout = Layout {
     n_next: v(Curr, 5),
 };
 
+/// The layout of the cells of every `VarBaseMul` gate
+pub fn layout() -> Layout {
+    LAYOUT
+}
+
+/// The y coordinates of the points `base` and `-base` added by the set and unset bits of a VBSM chain,
+/// which only depend on the base and can be computed once for all the chains sharing it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BaseCache<F> {
+    /// y coordinate of `base`
+    pub pos_y: F,
+    /// y coordinate of `-base`
+    pub neg_y: F,
+}
+
+impl<F: FftField> BaseCache<F> {
+    /// Computes the cache of a base point
+    pub fn new(base: (F, F)) -> Self {
+        BaseCache {
+            pos_y: base.1,
+            neg_y: neg_base_y(base),
+        }
+    }
+
+    /// Returns the y coordinate of the point added for the bit `b`, that is `(2b - 1) * base.y`
+    /// as in the constraint on the first slope: `base.y` when the bit is set and `-base.y` otherwise
+    pub fn select_y(&self, b: Bit<F>) -> F {
+        if b.value().is_zero() {
+            self.neg_y
+        } else {
+            self.pos_y
+        }
+    }
+}
+
+/// Returns the y coordinate of `-base`, the point added by the unset bits of a VBSM chain
+pub fn neg_base_y<F: FftField>(base: (F, F)) -> F {
+    -base.1
+}
+
 pub struct VarbaseMulResult<F> {
     pub acc: (F, F),
     pub n: F,
+    /// The number of zero bits prepended to the scalar to fill its last chunk
+    pub pad_bits: usize,
+    /// The scalar accumulated before and after each chunk, as written to its `n` and `n'` cells
+    pub chunk_ns: Vec<(F, F)>,
+}
+
+impl<F: FftField> VarbaseMulResult<F> {
+    /// Returns the scalar `n` as `len` bits, least significant first,
+    /// truncating its higher bits or padding it with zeros
+    pub fn n_bits(&self, len: usize) -> Vec<bool> {
+        let mut bits = self.n.to_bits();
+        bits.resize(len, false);
+        bits
+    }
+}
+
+/// Pads with leading zeros, or truncates leading zeros of, the most significant first bits of a scalar
+/// so that they are exactly `fixed_width` long, and the VBSM chain has the same length for every caller.
+/// Fails if a set bit would be truncated.
+pub fn canonical_bits(scalar_bits: &[bool], fixed_width: usize) -> Result<Vec<bool>, String> {
+    if scalar_bits.len() <= fixed_width {
+        let mut bits = vec![false; fixed_width - scalar_bits.len()];
+        bits.extend_from_slice(scalar_bits);
+        return Ok(bits);
+    }
+    let (truncated, bits) = scalar_bits.split_at(scalar_bits.len() - fixed_width);
+    if truncated.iter().any(|b| *b) {
+        return Err(format!("the scalar does not fit in {} bits", fixed_width));
+    }
+    Ok(bits.to_vec())
+}
+
+/// Coefficients of the short Weierstrass curve `y^2 = x^3 + a * x + b` of the points of a VBSM chain
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CurveParams<F> {
+    pub a: F,
+    pub b: F,
 }
 
+impl<F: FftField> CurveParams<F> {
+    /// The coefficients of a curve defined over the field of the circuit
+    pub fn of<P: SWModelParameters<BaseField = F>>() -> Self {
+        CurveParams {
+            a: P::COEFF_A,
+            b: P::COEFF_B,
+        }
+    }
+
+    /// Whether the affine point `p` lies on the curve
+    pub fn is_on_curve(&self, p: (F, F)) -> bool {
+        p.1.square() == (p.0.square() + self.a) * p.0 + self.b
+    }
+}
+
+/// Fills the witness of a VBSM chain, panicking on the errors reported by [try_witness].
+/// The most significant first bits are padded with leading zeros up to a multiple of 5,
+/// which leaves `n` unchanged but starts the accumulation with one step per pad bit,
+/// each of them taking `acc` to `2 * acc - base`.
+///
+/// In debug builds, also panics when `acc0` is the base, whose first slope divides by zero,
+/// or when `base` and `acc0` do not both lie on the `curve`. The point at infinity, which
+/// would be the usual mistake, has no affine coordinates and is caught by the latter.
 pub fn witness<F: FftField + std::fmt::Display>(
     w: &mut [Vec<F>; COLUMNS],
     row0: usize,
     base: (F, F),
     bits: &[bool],
     acc0: (F, F),
+    curve: &CurveParams<F>,
 ) -> VarbaseMulResult<F> {
+    debug_assert_ne!(
+        acc0, base,
+        "the initial accumulator of a VBSM chain cannot be its base"
+    );
+    debug_assert!(
+        curve.is_on_curve(base) && curve.is_on_curve(acc0),
+        "the base and the initial accumulator of a VBSM chain must lie on the same curve"
+    );
+    let res = witness_with_trace(w, row0, base, bits, acc0).0;
+    let num_chunks = (res.pad_bits + bits.len()) / LAYOUT.bits.len();
+    debug_assert!(
+        (0..num_chunks).all(|i| verify_zero_companion(w, row0 + 2 * i).is_ok()),
+        "the cells of a VBSM chunk were not filled on its Zero companion row"
+    );
+    res
+}
+
+/// Clears the cells of the `Zero` companion row of the `VarBaseMul` row `row` that the layout leaves
+/// unused, so that the companion only holds the `Next` cells read by the gate
+pub fn fill_zero_companion<F: FftField>(w: &mut [Vec<F>; COLUMNS], row: usize) {
+    let used: Vec<_> = LAYOUT
+        .all_variables()
+        .into_iter()
+        .filter(|var| var.row == Next)
+        .map(|var| var.col)
+        .collect();
+    for (col, column) in w.iter_mut().enumerate() {
+        if !used.contains(&Column::Witness(col)) {
+            column[row + 1] = F::zero();
+        }
+    }
+}
+
+/// Checks that the `Zero` companion row of the `VarBaseMul` row `row` holds the `Next` cells of its chunk,
+/// that is that the constraints of the gate hold when read across the two rows
+pub fn verify_zero_companion<F: FftField>(
+    w: &[Vec<F>; COLUMNS],
+    row: usize,
+) -> Result<(), VarbaseMulError> {
+    match VarbaseMul::residuals(w, row).first() {
+        None => Ok(()),
+        Some((tag, _)) => Err((*tag).into()),
+    }
+}
+
+/// Order of the bits of a scalar
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitOrder {
+    /// The most significant bit comes first, as expected by [witness]
+    MsbFirst,
+    /// The least significant bit comes first, as in little-endian decompositions
+    LsbFirst,
+}
+
+/// Fills the witness of a VBSM chain like [witness], from bits given in the `order` of the caller.
+/// The chain always consumes the most significant bit first, so that `n` is the same scalar in both orders.
+pub fn witness_with_order<F: FftField + std::fmt::Display>(
+    w: &mut [Vec<F>; COLUMNS],
+    row0: usize,
+    base: (F, F),
+    bits: &[bool],
+    acc0: (F, F),
+    curve: &CurveParams<F>,
+    order: BitOrder,
+) -> VarbaseMulResult<F> {
+    match order {
+        BitOrder::MsbFirst => witness(w, row0, base, bits, acc0, curve),
+        BitOrder::LsbFirst => {
+            let bits_msb: Vec<_> = bits.iter().rev().copied().collect();
+            witness(w, row0, base, &bits_msb, acc0, curve)
+        }
+    }
+}
+
+/// Fills the witness of a VBSM chain like [witness], from the affine points of a short Weierstrass curve
+/// over the field of the circuit rather than from their coordinates, so that the base and the
+/// accumulator cannot come from different curves. Panics if either point is the point at infinity.
+pub fn witness_affine<P>(
+    w: &mut [Vec<P::BaseField>; COLUMNS],
+    row0: usize,
+    base: GroupAffine<P>,
+    bits: &[bool],
+    acc0: GroupAffine<P>,
+) -> VarbaseMulResult<P::BaseField>
+where
+    P: SWModelParameters,
+    P::BaseField: FftField + std::fmt::Display,
+{
+    assert!(
+        !base.infinity && !acc0.infinity,
+        "VBSM points cannot be the point at infinity"
+    );
+    witness(
+        w,
+        row0,
+        (base.x, base.y),
+        bits,
+        (acc0.x, acc0.y),
+        &CurveParams::of::<P>(),
+    )
+}
+
+/// Fills the witness of a VBSM chain like [witness], and also returns for every chunk
+/// the accumulators written to its 6 slots, from its input to the output of its last bit
+pub fn witness_with_trace<F: FftField + std::fmt::Display>(
+    w: &mut [Vec<F>; COLUMNS],
+    row0: usize,
+    base: (F, F),
+    bits: &[bool],
+    acc0: (F, F),
+) -> (VarbaseMulResult<F>, Vec<[(F, F); 6]>) {
+    fill_padded(w, row0, base, &BaseCache::new(base), bits, acc0)
+}
+
+/// Fills the witness of a VBSM chain like [witness], reading the y coordinates of `base` and
+/// `-base` from a [BaseCache] computed once for every chain sharing the base
+pub fn witness_precomputed<F: FftField + std::fmt::Display>(
+    w: &mut [Vec<F>; COLUMNS],
+    row0: usize,
+    base: (F, F),
+    cache: &BaseCache<F>,
+    bits: &[bool],
+    acc0: (F, F),
+) -> VarbaseMulResult<F> {
+    fill_padded(w, row0, base, cache, bits, acc0).0
+}
+
+/// Pads the bits to a multiple of 5 and fills the witness, as described in [witness]
+fn fill_padded<F: FftField + std::fmt::Display>(
+    w: &mut [Vec<F>; COLUMNS],
+    row0: usize,
+    base: (F, F),
+    cache: &BaseCache<F>,
+    bits: &[bool],
+    acc0: (F, F),
+) -> (VarbaseMulResult<F>, Vec<[(F, F); 6]>) {
+    let bits_per_chunk = LAYOUT.bits.len();
+    let pad_bits = (bits_per_chunk - bits.len() % bits_per_chunk) % bits_per_chunk;
+    let mut padded = vec![false; pad_bits];
+    padded.extend_from_slice(bits);
+    let rows = (row0..).step_by(2).map(|row| (row, row + 1));
+    let (res, trace) = fill_chunks(w, rows, base, cache, &padded, acc0, None, bits_per_chunk)
+        .unwrap_or_else(|e| panic!("{}", e));
+    for i in 0..trace.len() {
+        fill_zero_companion(w, row0 + 2 * i);
+    }
+    (VarbaseMulResult { pad_bits, ..res }, trace)
+}
+
+/// Fills the witness of a VBSM chain from `row0`, returning an error instead of panicking
+/// when the number of bits is not a multiple of 5.
+/// The scalar `n` is accumulated from zero, unless the chain continues a previous one whose
+/// scalar is passed as `continuation`.
+pub fn try_witness<F: FftField>(
+    w: &mut [Vec<F>; COLUMNS],
+    row0: usize,
+    base: (F, F),
+    bits: &[bool],
+    acc0: (F, F),
+    continuation: Option<F>,
+) -> Result<VarbaseMulResult<F>, WitnessError> {
+    let rows = (row0..).step_by(2).map(|row| (row, row + 1));
+    try_witness_on_rows(w, rows, base, bits, acc0, continuation)
+}
+
+/// Fills the witness of a VBSM chain like [try_witness], but places each chunk of 5 bits on the
+/// next `(vbmul_row, zero_row)` pair yielded by `rows` instead of on consecutive rows from `row0`.
+/// The proof system reads the `Next` cells of a gate from the row following it, so a chain can
+/// only be proven when every `zero_row` is `vbmul_row + 1`.
+pub fn try_witness_on_rows<F: FftField>(
+    w: &mut [Vec<F>; COLUMNS],
+    rows: impl Iterator<Item = (usize, usize)>,
+    base: (F, F),
+    bits: &[bool],
+    acc0: (F, F),
+    continuation: Option<F>,
+) -> Result<VarbaseMulResult<F>, WitnessError> {
+    fill_chunks(
+        w,
+        rows,
+        base,
+        &BaseCache::new(base),
+        bits,
+        acc0,
+        continuation,
+        LAYOUT.bits.len(),
+    )
+    .map(|(res, _)| res)
+}
+
+/// Fills the witness of a VBSM chain from `row0` like [try_witness], but with `bits_per_chunk`
+/// bits on each pair of rows. Fails when the chunks do not fit in the bit slots of the layout,
+/// or when the number of bits is not a multiple of `bits_per_chunk`.
+/// Only chunks of 5 bits fill every slot of the layout, so only they yield a witness
+/// satisfying the `VarBaseMul` gate.
+pub fn witness_with_chunking<F: FftField>(
+    w: &mut [Vec<F>; COLUMNS],
+    row0: usize,
+    base: (F, F),
+    bits: &[bool],
+    acc0: (F, F),
+    bits_per_chunk: usize,
+) -> Result<VarbaseMulResult<F>, WitnessError> {
+    let rows = (row0..).step_by(2).map(|row| (row, row + 1));
+    fill_chunks(
+        w,
+        rows,
+        base,
+        &BaseCache::new(base),
+        bits,
+        acc0,
+        None,
+        bits_per_chunk,
+    )
+    .map(|(res, _)| res)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn fill_chunks<F: FftField>(
+    w: &mut [Vec<F>; COLUMNS],
+    mut rows: impl Iterator<Item = (usize, usize)>,
+    base: (F, F),
+    cache: &BaseCache<F>,
+    bits: &[bool],
+    acc0: (F, F),
+    continuation: Option<F>,
+    bits_per_chunk: usize,
+) -> Result<(VarbaseMulResult<F>, Vec<[(F, F); 6]>), WitnessError> {
     let l = LAYOUT;
-    let bits: Vec<_> = bits.iter().map(|b| F::from(*b as u64)).collect();
-    let bits_per_chunk = 5;
-    assert_eq!(bits_per_chunk * (bits.len() / bits_per_chunk), bits.len());
+    if bits_per_chunk == 0 || bits_per_chunk > l.bits.len() {
+        return Err(WitnessError::InvalidChunk {
+            chunk: bits_per_chunk,
+            slots: l.bits.len(),
+        });
+    }
+    let bits: Vec<Bit<F>> = bits.iter().map(|b| Bit::from(*b)).collect();
+    if bits.len() % bits_per_chunk != 0 {
+        return Err(WitnessError::ChunkSize {
+            bits: bits.len(),
+            chunk: bits_per_chunk,
+        });
+    }
 
     let mut acc = acc0;
-    let mut n_acc = F::zero();
+    let mut n_acc = continuation.unwrap_or_else(F::zero);
+    // the slots left unused by shorter chunks are zero
+    let mut trace = vec![];
+    let mut chunk_ns = vec![];
     for (chunk, bs) in bits.chunks(bits_per_chunk).enumerate() {
-        let row = row0 + 2 * chunk;
+        let rows = rows.next().ok_or(WitnessError::MissingRows {
+            chunks: bits.len() / bits_per_chunk,
+        })?;
 
-        set(w, row, l.n_prev, n_acc);
+        let mut accs = [(F::zero(), F::zero()); 6];
+        accs[0] = acc;
+        let n_prev = n_acc;
+        set(w, rows, l.n_prev, n_prev)?;
         for (i, bs) in bs.iter().enumerate().take(bits_per_chunk) {
             n_acc.double_in_place();
-            n_acc += bs;
+            n_acc += bs.value();
             acc = single_bit_witness(
                 w,
-                row,
+                rows,
+                (chunk, i),
                 l.bits[i],
                 l.base,
                 l.ss[i],
@@ -317,25 +986,229 @@ pub fn witness<F: FftField + std::fmt::Display>(
                 l.accs[i + 1],
                 *bs,
                 base,
+                cache.select_y(*bs),
                 acc,
-            );
+            )?;
+            accs[i + 1] = acc;
+        }
+        set(w, rows, l.n_next, n_acc)?;
+        chunk_ns.push((n_prev, n_acc));
+        trace.push(accs);
+    }
+    Ok((
+        VarbaseMulResult {
+            acc,
+            n: n_acc,
+            pad_bits: 0,
+            chunk_ns,
+        },
+        trace,
+    ))
+}
+
+/// Checks that the VBSM chain filled from `row0` accumulates its scalar from zero,
+/// or from `continuation` if it explicitly continues a previous chain
+pub fn verify_chain_start<F: FftField>(
+    w: &[Vec<F>; COLUMNS],
+    row0: usize,
+    continuation: Option<F>,
+) -> Result<(), WitnessError> {
+    if get(w, row0, LAYOUT.n_prev) == continuation.unwrap_or_else(F::zero) {
+        Ok(())
+    } else {
+        Err(WitnessError::UnexpectedStart)
+    }
+}
+
+/// Returns the witness of a VBSM chain filled by [witness] in row-major order,
+/// two rows of `COLUMNS` cells for every chunk of 5 bits, the last one completed by the pad bits
+pub fn witness_snapshot<F: FftField + std::fmt::Display>(
+    base: (F, F),
+    bits: &[bool],
+    acc0: (F, F),
+) -> Vec<Vec<F>> {
+    let bits_per_chunk = LAYOUT.bits.len();
+    let rows = 2 * ((bits.len() + bits_per_chunk - 1) / bits_per_chunk);
+    let mut w: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); rows]);
+    witness_with_trace(&mut w, 0, base, bits, acc0);
+    (0..rows)
+        .map(|row| w.iter().map(|column| column[row]).collect())
+        .collect()
+}
+
+/// Packs the `VarBaseMul` row `vbmul_row` and its `Zero` companion row into a single
+/// dense row, the first `COLUMNS` cells holding the former and the rest the latter
+pub fn pack_vbmul_rows<F: FftField>(w: &[Vec<F>; COLUMNS], vbmul_row: usize) -> [F; 2 * COLUMNS] {
+    let mut packed = [F::zero(); 2 * COLUMNS];
+    for (col, column) in w.iter().enumerate() {
+        packed[col] = column[vbmul_row];
+        packed[COLUMNS + col] = column[vbmul_row + 1];
+    }
+    packed
+}
+
+/// Restores the two rows packed by [pack_vbmul_rows] into the witness, from `vbmul_row`
+pub fn unpack_vbmul_rows<F: FftField>(
+    packed: &[F; 2 * COLUMNS],
+    w: &mut [Vec<F>; COLUMNS],
+    vbmul_row: usize,
+) {
+    for (col, column) in w.iter_mut().enumerate() {
+        column[vbmul_row] = packed[col];
+        column[vbmul_row + 1] = packed[COLUMNS + col];
+    }
+}
+
+/// Reads the `k`-th bit of the scalar from a VBSM chain filled by [witness] from `row0`,
+/// where bits are counted most significant first, from the zeros [witness] padded them with.
+/// The bits passed to [witness] therefore start at `k = pad_bits`, as returned in [VarbaseMulResult].
+pub fn bit_at<F: FftField>(w: &[Vec<F>; COLUMNS], row0: usize, k: usize) -> bool {
+    let bits_per_chunk = LAYOUT.bits.len();
+    let row = row0 + 2 * (k / bits_per_chunk);
+    get(w, row, LAYOUT.bits[k % bits_per_chunk]) == F::one()
+}
+
+/// Reads the bits of the `num_chunks` chunks of a VBSM chain filled by [witness] from `start_row`,
+/// most significant first as they were passed to [witness], along with the zeros it padded them with.
+/// Fails if a bit cell is neither 0 nor 1.
+pub fn read_bits<F: FftField>(
+    w: &[Vec<F>; COLUMNS],
+    start_row: usize,
+    num_chunks: usize,
+) -> Result<Vec<bool>, WitnessError> {
+    (0..num_chunks)
+        .flat_map(|chunk| LAYOUT.bits.iter().map(move |b| (start_row + 2 * chunk, *b)))
+        .map(|(row, b)| Bit::try_from(get(w, row, b)).map(|bit| bit == Bit::one()))
+        .collect()
+}
+
+/// Counts the set bits of the scalar of a VBSM chain filled by [witness] over the `num_rows`
+/// rows starting at `row0`, two per chunk of 5 bits, where any nonzero bit cell counts as set
+pub fn hamming_weight<F: FftField>(w: &[Vec<F>; COLUMNS], row0: usize, num_rows: usize) -> usize {
+    (row0..row0 + num_rows)
+        .step_by(2)
+        .map(|row| {
+            LAYOUT
+                .bits
+                .iter()
+                .filter(|b| !get(w, row, **b).is_zero())
+                .count()
+        })
+        .sum()
+}
+
+/// Checks that the VBSM chain filled by [witness] over the `num_rows` rows starting at `row0`
+/// multiplies by the scalar `expected`, by reading the `n` of its last chunk
+pub fn assert_scalar_eq<F: FftField>(
+    w: &[Vec<F>; COLUMNS],
+    row0: usize,
+    num_rows: usize,
+    expected: F,
+) -> Result<(), String> {
+    if num_rows < 2 || num_rows % 2 != 0 {
+        return Err(format!(
+            "a VBSM chain spans an even number of rows, not {}",
+            num_rows
+        ));
+    }
+    let n = get(w, row0 + num_rows - 2, LAYOUT.n_next);
+    if n == expected {
+        Ok(())
+    } else {
+        Err(format!(
+            "the VBSM chain at row {} multiplies by {}, not {}",
+            row0, n, expected
+        ))
+    }
+}
+
+/// Affine addition of two points of a short Weierstrass curve with `a = 0`,
+/// assuming neither is the point at infinity and they are not opposite
+fn add_points<F: FftField>(p: (F, F), q: (F, F)) -> (F, F) {
+    let s = if p == q {
+        (p.0.square().double() + p.0.square()) / p.1.double()
+    } else {
+        (q.1 - p.1) / (q.0 - p.0)
+    };
+    let x = s.square() - p.0 - q.0;
+    let y = (p.0 - x) * s - p.1;
+    (x, y)
+}
+
+/// Affine addition of two points of a short Weierstrass curve with `a = 0`,
+/// where `None` is the point at infinity
+#[cfg(any(test, feature = "testing"))]
+fn add_points_complete<F: FftField>(p: Option<(F, F)>, q: Option<(F, F)>) -> Option<(F, F)> {
+    match (p, q) {
+        (None, r) | (r, None) => r,
+        (Some(p), Some(q)) if p.0 == q.0 && (p.1 != q.1 || p.1.is_zero()) => None,
+        (Some(p), Some(q)) => Some(add_points(p, q)),
+    }
+}
+
+/// Multiplies `point` by the most significant first `bits` with a Montgomery ladder
+#[cfg(any(test, feature = "testing"))]
+fn ladder<F: FftField>(bits: impl Iterator<Item = bool>, point: (F, F)) -> Option<(F, F)> {
+    let (mut r0, mut r1) = (None, Some(point));
+    for b in bits {
+        if b {
+            r0 = add_points_complete(r0, r1);
+            r1 = add_points_complete(r1, r1);
+        } else {
+            r1 = add_points_complete(r0, r1);
+            r0 = add_points_complete(r0, r0);
         }
-        set(w, row, l.n_next, n_acc);
     }
-    VarbaseMulResult { acc, n: n_acc }
+    r0
+}
+
+/// Reference implementation of the VBSM chain with a Montgomery ladder instead of the
+/// double-and-add recurrence of [witness], to cross-check it in tests.
+/// For `n` bits encoding the scalar `x`, the chain computes
+/// `[2^n]acc0 + [2x + 1 - 2^n]base = [2^n]acc0 + [2x + 1]base - [2^n]base`,
+/// where each term is computed by a separate ladder.
+#[cfg(any(test, feature = "testing"))]
+pub fn montgomery_ladder_scalar_mul<F: FftField>(
+    base: (F, F),
+    bits: &[bool],
+    acc0: (F, F),
+) -> Option<(F, F)> {
+    let two_to_n = || std::iter::once(true).chain(bits.iter().map(|_| false));
+    let shifted_acc = ladder(two_to_n(), acc0);
+    let odd = ladder(bits.iter().copied().chain(std::iter::once(true)), base);
+    let shifted_base = ladder(two_to_n(), base).map(|(x, y)| (x, -y));
+    add_points_complete(add_points_complete(shifted_acc, odd), shifted_base)
+}
+
+/// The equation of the docstring a VBSM constraint comes from,
+/// along with the bit of the chunk it constrains
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ConstraintTag {
+    /// n' = 2^5 * n + 2^4 b0 + 2^3 b1 + 2^2 b2 + 2^1 b3 + b4
+    NRecomposition,
+    /// b * (b - 1) = 0
+    Boolean(usize),
+    /// (input.x - base.x) * s1 = input.y – (2b-1) * base.y
+    S1Slope(usize),
+    /// output.x = base.x + s2^2 - s1^2
+    OutputX(usize),
+    /// output.y = (input.x – output.x) * s2 - input.y
+    OutputY(usize),
 }
 
 /// Implementation of the VarbaseMul gate
 pub struct VarbaseMul<F>(PhantomData<F>);
 
-impl<F> Argument<F> for VarbaseMul<F>
-where
-    F: FftField,
-{
-    const ARGUMENT_TYPE: ArgumentType = ArgumentType::Gate(GateType::VarBaseMul);
-    const CONSTRAINTS: u32 = 21;
+impl<F: FftField> VarbaseMul<F> {
+    /// Returns the selector of the VarBaseMul gate, which gates the constraints
+    /// so that they only apply on the rows where [CircuitGate::vbmul] is one.
+    pub fn selector_expr() -> E<F> {
+        index(GateType::VarBaseMul)
+    }
 
-    fn constraints() -> Vec<E<F>> {
+    /// Returns the constraints of the gate, in the same order as [Argument::constraints],
+    /// each of them tagged with the equation it comes from
+    pub fn constraints_annotated() -> Vec<(ConstraintTag, E<F>)> {
         let Layout {
             base,
             accs,
@@ -355,17 +1228,257 @@ where
 
         let n_prev = E::Cell(n_prev);
         let n_next = E::Cell(n_next);
-        let mut res = vec![
+        let mut res = vec![(
+            ConstraintTag::NRecomposition,
             n_next
                 - bits
                     .iter()
                     .fold(n_prev, |acc, b| E::Cell(*b) + acc.double()),
-        ];
+        )];
 
         for i in 0..5 {
-            res.append(&mut constraint(i));
+            let tags = [
+                ConstraintTag::Boolean(i),
+                ConstraintTag::S1Slope(i),
+                ConstraintTag::OutputX(i),
+                ConstraintTag::OutputY(i),
+            ];
+            res.extend(tags.into_iter().zip(constraint(i)));
         }
 
         res
     }
+
+    /// Returns the number of field multiplications, squarings and inversions performed by [witness]
+    /// for a scalar of `num_bits` bits, counting the pad bits that complete the last chunk.
+    /// Each bit costs 13 of them:
+    /// * 3 multiplications or squarings for the common denominator `d` of `s1` and `s2`
+    /// * 3 multiplications and 1 inversion to invert both denominators at once
+    /// * 2 for `s1` and its square, 3 for `s2` and its square
+    /// * 1 for the output `y`
+    ///
+    /// The y coordinate added for a bit is selected by [BaseCache::select_y], without multiplying.
+    pub fn mul_count(num_bits: usize) -> usize {
+        let bits_per_chunk = LAYOUT.bits.len();
+        let num_chunks = (num_bits + bits_per_chunk - 1) / bits_per_chunk;
+        13 * num_chunks * bits_per_chunk
+    }
+
+    /// Returns the constraints of the final correction of the chain, `(d_0 == 0) ? Q - P : Q`,
+    /// which are not part of the gate. They apply to a single row laid out as
+    ///
+    /// |  0 |  1 |  2 |  3 |   4 |  5 |  6 | 7 |
+    /// | -------------------------------------- |
+    /// | xP | yP | xQ | yQ | d_0 | xR | yR | s |
+    ///
+    /// where `R` is the corrected output and `s` the slope of `Q - P`, which is left
+    /// unconstrained when `d_0` is one.
+    pub fn correction_constraint() -> Vec<E<F>> {
+        let cell = |col| E::Cell(v(Curr, col));
+        let (xp, yp, xq, yq) = (cell(0), cell(1), cell(2), cell(3));
+        let (d0, xr, yr, s) = (cell(4), cell(5), cell(6), cell(7));
+        let not_d0 = E::one() - d0.clone();
+
+        // Q - P = Q + (xP, -yP)
+        //   s = (yQ + yP) / (xQ - xP)
+        //   x = s^2 - xQ - xP
+        //   y = s * (xQ - x) - yQ
+        let x_sub = s.clone() * s.clone() - xq.clone() - xp.clone();
+        let y_sub = s.clone() * (xq.clone() - xr.clone()) - yq.clone();
+        vec![
+            // boolean constrain d_0
+            d0.clone() * d0.clone() - d0.clone(),
+            // constrain s when subtracting
+            not_d0.clone() * ((xq.clone() - xp) * s - (yq.clone() + yp)),
+            // constrain the output
+            xr - (d0.clone() * xq + not_d0.clone() * x_sub),
+            yr - (d0 * yq + not_d0 * y_sub),
+        ]
+    }
+
+    /// Returns the indices, in the order of [Argument::constraints], of the constraints that
+    /// reference the witness column `col` on either of the two rows of the gate
+    pub fn constraints_touching(col: usize) -> Vec<usize> {
+        Self::constraints_annotated()
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, c))| c.references_column(Column::Witness(col)))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Evaluates every constraint on the witness, with `row` as the current row and `next` as the next one
+    fn evaluate_row(
+        witness: &[Vec<F>; COLUMNS],
+        row: usize,
+        next: usize,
+        domain: D<F>,
+        pt: F,
+    ) -> Vec<F> {
+        let evals = [
+            ProofEvaluations::dummy_with_witness_evaluations(array_init(|col| witness[col][row])),
+            ProofEvaluations::dummy_with_witness_evaluations(array_init(|col| witness[col][next])),
+        ];
+        let constants = Constants {
+            alpha: F::zero(),
+            beta: F::zero(),
+            gamma: F::zero(),
+            joint_combiner: F::zero(),
+            endo_coefficient: F::zero(),
+            mds: vec![],
+        };
+        Self::constraints()
+            .iter()
+            .map(|c| {
+                c.evaluate_(domain, pt, &evals, &constants)
+                    .expect("VarBaseMul constraints only use witness cells")
+            })
+            .collect()
+    }
+
+    /// Evaluates the constraints, combined with the powers of `alpha`, at every point of the domain,
+    /// where the witness holds one row per point and the last row wraps around to the first one.
+    /// The values are zero on the rows of a valid VBSM witness, before the selector is applied.
+    pub fn evaluate_over_domain(witness: &[Vec<F>; COLUMNS], domain: D<F>, alpha: F) -> Vec<F> {
+        let n = domain.size();
+        domain
+            .elements()
+            .enumerate()
+            .map(|(row, pt)| {
+                Self::evaluate_row(witness, row, (row + 1) % n, domain, pt)
+                    .into_iter()
+                    .rev()
+                    .fold(F::zero(), |acc, c| acc * alpha + c)
+            })
+            .collect()
+    }
+
+    /// Evaluates each of the 21 constraints, in the order of [VarbaseMul::constraints], on the VBSM row
+    /// `row` of the witness and the row after it. A constraint holds when its residual is zero.
+    pub fn evaluate_residuals(witness: &[Vec<F>; COLUMNS], row: usize) -> Vec<F> {
+        let domain = D::<F>::new(2).expect("a domain of size 2 always exists");
+        Self::evaluate_row(witness, row, row + 1, domain, domain.group_gen)
+    }
+
+    /// Returns the constraints that do not vanish on the VBSM row `row` of the witness,
+    /// along with their value
+    pub fn residuals(witness: &[Vec<F>; COLUMNS], row: usize) -> Vec<(ConstraintTag, F)> {
+        Self::constraints_annotated()
+            .into_iter()
+            .map(|(tag, _)| tag)
+            .zip(Self::evaluate_residuals(witness, row))
+            .filter(|(_, residual)| !residual.is_zero())
+            .collect()
+    }
+
+    /// Checks that every constraint evaluates to zero on an all-zero row followed by another,
+    /// as padding rows are laid out, so that padding cannot violate the gate even before the
+    /// selector, which is off on these rows, is applied
+    pub fn vanishes_on_padding() -> bool {
+        let domain = D::<F>::new(2).expect("a domain of size 2 always exists");
+        let padding: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 2]);
+        Self::evaluate_row(&padding, 0, 1, domain, domain.group_gen)
+            .iter()
+            .all(|c| c.is_zero())
+    }
+}
+
+impl<F> Argument<F> for VarbaseMul<F>
+where
+    F: FftField,
+{
+    const ARGUMENT_TYPE: ArgumentType = ArgumentType::Gate(GateType::VarBaseMul);
+    const CONSTRAINTS: u32 = 21;
+
+    fn constraints() -> Vec<E<F>> {
+        let constraints: Vec<_> = Self::constraints_annotated()
+            .into_iter()
+            .map(|(_, c)| c)
+            .collect();
+        // the n recomposition, and 4 constraints for each of the 5 bits
+        debug_assert_eq!(constraints.len(), Self::CONSTRAINTS as usize);
+        constraints
+    }
+
+    /// The gate spans the rows of the cells of its layout, that is its row
+    /// and the `Zero` companion row holding the `Next` cells
+    fn gate_footprint() -> GateFootprint {
+        let rows = LAYOUT
+            .all_variables()
+            .iter()
+            .map(|var| var.row.shift() + 1)
+            .max()
+            .unwrap_or(1);
+        GateFootprint {
+            rows,
+            constraints: Self::CONSTRAINTS,
+            next_row_used: rows > 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+    use ark_ff::Zero;
+    use mina_curves::pasta::{
+        fp::Fp as F,
+        pallas::{Affine as Other, PallasParameters},
+    };
+    use std::collections::HashSet;
+
+    #[test]
+    fn set_non_witness_column() {
+        let mut w: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 2]);
+        let col = Column::Index(GateType::VarBaseMul);
+        assert_eq!(
+            set(&mut w, (0, 1), Variable { row: Curr, col }, F::one()),
+            Err(WitnessError::NotWitnessColumn(col))
+        );
+        assert_eq!(set(&mut w, (0, 1), LAYOUT.n_prev, F::one()), Ok(()));
+    }
+
+    #[test]
+    fn try_witness_chunk_size() {
+        let mut w: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 2]);
+        let res = try_witness(
+            &mut w,
+            0,
+            (F::one(), F::one()),
+            &[true; 7],
+            (F::zero(), F::one()),
+            None,
+        );
+        assert_eq!(
+            res.err(),
+            Some(WitnessError::ChunkSize { bits: 7, chunk: 5 })
+        );
+    }
+
+    #[test]
+    fn layout_all_variables() {
+        // the 15 columns of both rows but 6 on the first one and 12, 13, 14 on the second one
+        let vars = LAYOUT.all_variables();
+        assert_eq!(vars.len(), 26);
+        assert_eq!(vars.iter().collect::<HashSet<_>>().len(), vars.len());
+
+        // the witness fills every cell of the layout
+        let unset = F::from(0xdeadu64);
+        let mut w: [Vec<F>; COLUMNS] = array_init(|_| vec![unset; 2]);
+        let base = Other::prime_subgroup_generator();
+        let acc0 = (base.into_projective() + base.into_projective()).into_affine();
+        witness(
+            &mut w,
+            0,
+            (base.x, base.y),
+            &[true, false, true, true, false],
+            (acc0.x, acc0.y),
+            &CurveParams::of::<PallasParameters>(),
+        );
+        for var in vars {
+            assert_ne!(get(&w, 0, var), unset, "{:?} is not set", var);
+        }
+    }
 }
@@ -3,7 +3,10 @@ use crate::verifier::verify;
 use crate::{
     circuits::{
         gate::{CircuitGate, GateType},
-        polynomials::endosclmul,
+        polynomials::{
+            endosclmul,
+            varbasemul::{self, CurveParams},
+        },
         wires::*,
     },
     prover_index::testing::new_index_for_test,
@@ -16,7 +19,7 @@ use commitment_dlog::{commitment::CommitmentCurve, srs::endos};
 use groupmap::GroupMap;
 use mina_curves::pasta::{
     fp::Fp as F,
-    pallas::Affine as Other,
+    pallas::{Affine as Other, PallasParameters},
     vesta::{Affine, VestaParameters},
 };
 use oracle::{
@@ -144,3 +147,50 @@ fn endomul_test() {
         }
     }
 }
+
+#[test]
+fn endomul_matches_varbasemul_test() {
+    type ScalarField = <Other as AffineCurve>::ScalarField;
+    let num_bits = 128;
+    let vbsm_bits = 255;
+
+    let (endo_q, endo_r) = endos::<Other>();
+    let rng = &mut StdRng::from_seed([1; 32]);
+    let bits_lsb: Vec<_> = BitIteratorLE::new(F::rand(rng).into_repr())
+        .take(num_bits)
+        .collect();
+    let x = ScalarField::from_repr(<F as PrimeField>::BigInt::from_bits_le(&bits_lsb[..])).unwrap();
+    let bits_msb: Vec<_> = bits_lsb.iter().copied().rev().collect();
+
+    let base = Other::prime_subgroup_generator();
+    let acc0 = {
+        let t = Other::new(endo_q * base.x, base.y, false);
+        let p = t + base;
+        let acc = p + p;
+        (acc.x, acc.y)
+    };
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); num_bits / 4 + 1]);
+    let endo = endosclmul::gen_witness(&mut witness, 0, endo_q, (base.x, base.y), &bits_msb, acc0);
+
+    // the plain chain of n bits encoding y computes [2^n + 2y + 1]T from [2]T,
+    // so it reaches [k]T for y = (k - 2^n - 1) / 2
+    let k = ScalarChallenge(x).to_field(&endo_r);
+    let two_to_n = ScalarField::from(2u64).pow(&[vbsm_bits as u64]);
+    let y = (k - two_to_n - ScalarField::one()) / ScalarField::from(2u64);
+    let mut y_msb: Vec<_> = BitIteratorLE::new(y.into_repr()).take(vbsm_bits).collect();
+    y_msb.reverse();
+
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 2 * (vbsm_bits / 5)]);
+    let plain = varbasemul::witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &y_msb,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+
+    assert_eq!(endo.acc, plain.acc);
+}
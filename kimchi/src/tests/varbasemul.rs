@@ -2,28 +2,43 @@ use crate::prover::ProverProof;
 use crate::verifier::verify;
 use crate::{
     circuits::{
-        gate::{CircuitGate, GateType},
-        polynomials::varbasemul,
+        argument::{Argument, GateFootprint},
+        constraints::ConstraintSystem,
+        expr::{
+            count_mul_gates, l0_1, CacheId, Column, ConstantExpr, Constants, Environment, Expr,
+            Op2, Variable, E,
+        },
+        gate::{CircuitGate, CurrOrNext, GateType},
+        polynomials::{
+            complete_add::CompleteAdd,
+            endosclmul::EndosclMul,
+            varbasemul::{self, Bit, ConstraintTag, CurveParams, VarbaseMul, VarbaseMulError},
+        },
+        scalars::ProofEvaluations,
         wires::*,
     },
     prover_index::testing::new_index_for_test,
 };
 use ark_ec::{AffineCurve, ProjectiveCurve};
 use ark_ff::{BigInteger, BitIteratorLE, Field, One, PrimeField, UniformRand, Zero};
+use ark_poly::{EvaluationDomain, Radix2EvaluationDomain as D};
 use array_init::array_init;
 use colored::Colorize;
 use commitment_dlog::commitment::CommitmentCurve;
 use groupmap::GroupMap;
 use mina_curves::pasta::{
     fp::Fp as F,
-    pallas::Affine as Other,
+    pallas::{Affine as Other, PallasParameters},
     vesta::{Affine, VestaParameters},
 };
+use o1_utils::FieldHelpers;
 use oracle::{
     constants::PlonkSpongeConstantsKimchi,
     sponge::{DefaultFqSponge, DefaultFrSponge},
 };
-use rand::{rngs::StdRng, SeedableRng};
+use proptest::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::{HashMap, HashSet};
 use std::time::Instant;
 
 const PUBLIC: usize = 0;
@@ -90,6 +105,7 @@ fn varbase_mul_test() {
             (base.x, base.y),
             &bits_msb,
             acc,
+            &CurveParams::of::<PallasParameters>(),
         );
 
         let shift = <Other as AffineCurve>::ScalarField::from(2).pow(&[(bits_msb.len()) as u64]);
@@ -119,3 +135,1658 @@ fn varbase_mul_test() {
         }
     }
 }
+
+#[test]
+fn varbase_mul_selector_test() {
+    let mut gates = CircuitGate::<F>::create_vbmul(&[Wire::new(0), Wire::new(1)]);
+    gates.push(CircuitGate::zero(Wire::new(2)));
+    gates.extend(CircuitGate::<F>::create_vbmul(&[
+        Wire::new(3),
+        Wire::new(4),
+    ]));
+
+    let fp_sponge_params = oracle::pasta::fp_kimchi::params();
+    let cs = ConstraintSystem::<F>::create(gates, vec![], fp_sponge_params, PUBLIC).unwrap();
+
+    let mut index_evals = HashMap::new();
+    index_evals.insert(GateType::VarBaseMul, &cs.mull8);
+    let env = Environment {
+        constants: Constants {
+            alpha: F::zero(),
+            beta: F::zero(),
+            gamma: F::zero(),
+            joint_combiner: F::zero(),
+            endo_coefficient: cs.endo,
+            mds: vec![],
+        },
+        witness: &cs.coefficients8,
+        coefficient: &cs.coefficients8,
+        vanishes_on_last_4_rows: &cs.vanishes_on_last_4_rows,
+        z: &cs.mull8,
+        l0_1: l0_1(cs.domain.d1),
+        domain: cs.domain,
+        index: index_evals,
+        lookup: None,
+    };
+
+    // the selector is one on the VarBaseMul rows and zero everywhere else, padding included
+    let selector = VarbaseMul::<F>::selector_expr().evaluations(&env);
+    for (row, gate) in cs.gates.iter().enumerate() {
+        let expected = if gate.typ == GateType::VarBaseMul {
+            F::one()
+        } else {
+            F::zero()
+        };
+        assert_eq!(selector.evals[row], expected);
+    }
+}
+
+#[test]
+fn varbase_mul_chain_gates_test() {
+    // 51 chunks of 5 bits hold a 255-bit scalar
+    let gates = CircuitGate::<F>::create_vbmul_chain(10, 51);
+    assert_eq!(gates.len(), 102);
+    for (i, gate) in gates.iter().enumerate() {
+        let typ = if i % 2 == 0 {
+            GateType::VarBaseMul
+        } else {
+            GateType::Zero
+        };
+        assert_eq!(gate.typ, typ);
+        assert_eq!(gate.wires, Wire::new(10 + i));
+    }
+    assert!(CircuitGate::<F>::create_vbmul_chain(0, 0).is_empty());
+}
+
+#[test]
+fn varbase_mul_layout_test() {
+    let layout = varbasemul::layout();
+    assert_eq!(
+        layout.bits[2],
+        Variable {
+            col: Column::Witness(4),
+            row: CurrOrNext::Next,
+        }
+    );
+    assert_eq!(layout.bit(2), (CurrOrNext::Next, 4));
+    assert_eq!(
+        layout.base(),
+        ((CurrOrNext::Curr, 0), (CurrOrNext::Curr, 1))
+    );
+    assert_eq!(
+        layout.acc(5),
+        ((CurrOrNext::Next, 0), (CurrOrNext::Next, 1))
+    );
+    assert_eq!(layout.s(0), (CurrOrNext::Next, 7));
+    assert_eq!(layout.n_prev(), (CurrOrNext::Curr, 4));
+    assert_eq!(layout.n_next(), (CurrOrNext::Curr, 5));
+}
+
+/// Computes the VBSM accumulator with one inversion per denominator, as in the
+/// docstring of [varbasemul], to cross-check the batched inversions of the witness.
+fn reference_acc(base: (F, F), bits: &[bool], acc0: (F, F)) -> (F, F) {
+    bits.iter().fold(acc0, |(xp, yp), b| {
+        let b = F::from(*b as u64);
+        let s1 = (yp - base.1 * (b.double() - F::one())) / (xp - base.0);
+        let s1_squared = s1.square();
+        let s2 = yp.double() / (xp.double() + base.0 - s1_squared) - s1;
+        let xr = base.0 + s2.square() - s1_squared;
+        let yr = (xp - xr) * s2 - yp;
+        (xr, yr)
+    })
+}
+
+#[test]
+fn varbase_mul_batched_inversion_test() {
+    let num_bits = F::size_in_bits();
+    let rng = &mut StdRng::from_seed([1; 32]);
+
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+
+    for _ in 0..4 {
+        let x = F::rand(rng);
+        let bits_msb: Vec<_> = BitIteratorLE::new(x.into_repr())
+            .take(num_bits)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .collect();
+
+        let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 2 * num_bits / 5]);
+        let res = varbasemul::witness(
+            &mut witness,
+            0,
+            (base.x, base.y),
+            &bits_msb,
+            (acc.x, acc.y),
+            &CurveParams::of::<PallasParameters>(),
+        );
+
+        assert_eq!(
+            res.acc,
+            reference_acc((base.x, base.y), &bits_msb, (acc.x, acc.y))
+        );
+    }
+}
+
+#[test]
+fn varbase_mul_bit_at_test() {
+    // 0b1011000101 = 709
+    let bits_msb = [
+        true, false, true, true, false, false, false, true, false, true,
+    ];
+
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+
+    let row0 = 4;
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); row0 + 4]);
+    let res = varbasemul::witness(
+        &mut witness,
+        row0,
+        (base.x, base.y),
+        &bits_msb,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+    assert_eq!(res.n, F::from(709u64));
+
+    for (k, bit) in bits_msb.iter().enumerate() {
+        assert_eq!(varbasemul::bit_at(&witness, row0, k), *bit);
+    }
+}
+
+#[test]
+fn varbase_mul_symbolic_constraints_test() {
+    let rng = &mut StdRng::from_seed([2; 32]);
+
+    // a random point on the curve and a random chunk of bits
+    let t = Other::prime_subgroup_generator()
+        .mul(<Other as AffineCurve>::ScalarField::rand(rng).into_repr())
+        .into_affine();
+    let p = (t.into_projective() + t.into_projective()).into_affine();
+    let bits: Vec<bool> = (0..5).map(|_| bool::rand(rng)).collect();
+
+    // run the reference algorithm of the docstring, one bit at a time:
+    //   (xp - xt) * s1 = yp - (2b - 1) * yt
+    //   s1^2 - s2^2 = xt - xr
+    //   (xp - xr) * s2 = yr + yp
+    let mut accs = vec![(p.x, p.y)];
+    let mut slopes = vec![];
+    for b in bits.iter() {
+        let (xp, yp) = accs[accs.len() - 1];
+        let b = F::from(*b as u64);
+        let s1 = (yp - (b.double() - F::one()) * t.y) / (xp - t.x);
+        let s2 = yp.double() / (xp.double() + t.x - s1.square()) - s1;
+        let xr = t.x + s2.square() - s1.square();
+        let yr = (xp - xr) * s2 - yp;
+        accs.push((xr, yr));
+        slopes.push(s1);
+    }
+    let n_prev = F::from(7u64);
+    let n_next = bits
+        .iter()
+        .fold(n_prev, |acc, b| acc.double() + F::from(*b as u64));
+
+    // lay the assignment out as documented
+    // 0   1   2   3   4   5   6   7   8   9   10  11  12  13  14
+    // xT  yT  x0  y0  n   n'      x1  y1  x2  y2  x3  y3  x4  y4
+    // x5  y5  b0  b1  b2  b3  b4  s0  s1  s2  s3  s4
+    let mut curr = [F::zero(); COLUMNS];
+    let mut next = [F::zero(); COLUMNS];
+    curr[0] = t.x;
+    curr[1] = t.y;
+    curr[2] = accs[0].0;
+    curr[3] = accs[0].1;
+    curr[4] = n_prev;
+    curr[5] = n_next;
+    for (i, acc) in accs.iter().enumerate().take(5).skip(1) {
+        curr[5 + 2 * i] = acc.0;
+        curr[6 + 2 * i] = acc.1;
+    }
+    next[0] = accs[5].0;
+    next[1] = accs[5].1;
+    for (i, (b, s1)) in bits.iter().zip(slopes.iter()).enumerate() {
+        next[2 + i] = F::from(*b as u64);
+        next[7 + i] = *s1;
+    }
+
+    let evals = [
+        ProofEvaluations::dummy_with_witness_evaluations(curr),
+        ProofEvaluations::dummy_with_witness_evaluations(next),
+    ];
+    let constants = Constants {
+        alpha: F::zero(),
+        beta: F::zero(),
+        gamma: F::zero(),
+        joint_combiner: F::zero(),
+        endo_coefficient: F::zero(),
+        mds: vec![],
+    };
+    let domain = D::<F>::new(8).unwrap();
+    let pt = F::rand(rng);
+
+    let constraints = VarbaseMul::<F>::constraints();
+    assert_eq!(constraints.len(), 21);
+    for (i, c) in constraints.iter().enumerate() {
+        assert_eq!(
+            c.evaluate_(domain, pt, &evals, &constants).unwrap(),
+            F::zero(),
+            "constraint {} does not hold",
+            i
+        );
+    }
+}
+
+#[test]
+fn scalar_one_yields_base() {
+    type Fq = <Other as AffineCurve>::ScalarField;
+    let num_bits = F::size_in_bits();
+
+    // The chain run on the bits of x computes [2^n + 1 + 2x]T, so the even scalar k is
+    // obtained from x = (k - 2^n) / 2 followed by the correction of the docstring,
+    // (d_0 == 0) ? Q - T : Q. The odd representative of 1 is avoided on purpose, as
+    // the accumulator before its last step would be the point at infinity.
+    let shift = Fq::from(2u64).pow(&[num_bits as u64]);
+    let x = (Fq::one() - shift) / Fq::from(2u64);
+    let bits_msb: Vec<_> = BitIteratorLE::new(x.into_repr())
+        .take(num_bits)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc0 = (g + g).into_affine();
+
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 2 * num_bits / 5]);
+    let res = varbasemul::witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &bits_msb,
+        (acc0.x, acc0.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+    assert_eq!(res.acc, (acc0.x, acc0.y));
+
+    let q = Other::new(res.acc.0, res.acc.1, false);
+    let result = (q.into_projective() - g).into_affine();
+    assert_eq!(result, base);
+}
+
+#[test]
+fn varbase_mul_chain_start_test() {
+    // 0b1011000101 = 709
+    let bits_msb = [
+        true, false, true, true, false, false, false, true, false, true,
+    ];
+
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 4]);
+    varbasemul::witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &bits_msb,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+    assert_eq!(varbasemul::verify_chain_start(&witness, 0, None), Ok(()));
+
+    // a nonzero n_prev on the first row is rejected
+    witness[4][0] = F::from(3u64);
+    assert_eq!(
+        varbasemul::verify_chain_start(&witness, 0, None),
+        Err(varbasemul::WitnessError::UnexpectedStart)
+    );
+
+    // unless the chain explicitly continues a previous scalar
+    let continuation = F::from(3u64);
+    let res = varbasemul::try_witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &bits_msb,
+        (acc.x, acc.y),
+        Some(continuation),
+    )
+    .unwrap();
+    assert_eq!(res.n, F::from(3 * 1024 + 709u64));
+    assert_eq!(
+        varbasemul::verify_chain_start(&witness, 0, Some(continuation)),
+        Ok(())
+    );
+    assert_eq!(
+        varbasemul::verify_chain_start(&witness, 0, None),
+        Err(varbasemul::WitnessError::UnexpectedStart)
+    );
+}
+
+#[test]
+fn varbase_mul_pack_rows_test() {
+    let rng = &mut StdRng::from_seed([3; 32]);
+    let bits_msb: Vec<bool> = (0..10).map(|_| bool::rand(rng)).collect();
+
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 4]);
+    varbasemul::witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &bits_msb,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+
+    let mut restored: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 4]);
+    for vbmul_row in [0, 2] {
+        let packed = varbasemul::pack_vbmul_rows(&witness, vbmul_row);
+        for (col, column) in witness.iter().enumerate() {
+            assert_eq!(packed[col], column[vbmul_row]);
+            assert_eq!(packed[COLUMNS + col], column[vbmul_row + 1]);
+        }
+        varbasemul::unpack_vbmul_rows(&packed, &mut restored, vbmul_row);
+    }
+    assert_eq!(restored, witness);
+}
+
+#[test]
+fn varbase_mul_annotated_constraints_test() {
+    let annotated = VarbaseMul::<F>::constraints_annotated();
+    assert_eq!(annotated.len(), 21);
+    assert_eq!(annotated[0].0, ConstraintTag::NRecomposition);
+
+    let tags: HashSet<_> = annotated.iter().map(|(tag, _)| *tag).collect();
+    assert_eq!(tags.len(), annotated.len());
+
+    let constraints = VarbaseMul::<F>::constraints();
+    for ((_, annotated), c) in annotated.iter().zip(constraints.iter()) {
+        assert_eq!(annotated, c);
+    }
+}
+
+#[test]
+fn varbase_mul_witness_snapshot_test() {
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+
+    // one line per row, with the cells in hexadecimal
+    let parse = |snapshot: &str| -> Vec<Vec<F>> {
+        snapshot
+            .lines()
+            .map(|line| {
+                line.split(' ')
+                    .map(|cell| F::from_hex(cell).unwrap())
+                    .collect()
+            })
+            .collect()
+    };
+
+    let rows = varbasemul::witness_snapshot(
+        (base.x, base.y),
+        &[true, false, true, true, false],
+        (acc.x, acc.y),
+    );
+    assert_eq!(
+        rows,
+        parse(include_str!("test_vectors/varbasemul_witness.txt"))
+    );
+
+    // 12 bits, padded with 3 leading zeros into 3 chunks
+    let bits: Vec<bool> = "110100111001".chars().map(|c| c == '1').collect();
+    let rows = varbasemul::witness_snapshot((base.x, base.y), &bits, (acc.x, acc.y));
+    assert_eq!(
+        rows,
+        parse(include_str!("test_vectors/varbasemul_witness_chunks.txt"))
+    );
+
+    // 3 bits are padded with 2 leading zeros into a single chunk
+    let padded =
+        varbasemul::witness_snapshot((base.x, base.y), &[true, false, true], (acc.x, acc.y));
+    let unpadded = varbasemul::witness_snapshot(
+        (base.x, base.y),
+        &[false, false, true, false, true],
+        (acc.x, acc.y),
+    );
+    assert_eq!(padded.len(), 2);
+    assert_eq!(padded, unpadded);
+}
+
+#[test]
+fn varbase_mul_evaluate_over_domain_test() {
+    let rng = &mut StdRng::from_seed([4; 32]);
+    let bits_msb: Vec<bool> = (0..10).map(|_| bool::rand(rng)).collect();
+
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+
+    // two chunks fill a domain of 4 rows
+    let domain = D::<F>::new(4).unwrap();
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); domain.size()]);
+    varbasemul::witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &bits_msb,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+
+    let alpha = F::rand(rng);
+    let evals = VarbaseMul::evaluate_over_domain(&witness, domain, alpha);
+    assert_eq!(evals.len(), 4);
+    assert_eq!(evals[0], F::zero());
+    assert_eq!(evals[2], F::zero());
+
+    // nudging a slope of the second chunk breaks its row only
+    witness[7][3] += F::one();
+    let evals = VarbaseMul::evaluate_over_domain(&witness, domain, alpha);
+    assert_eq!(evals[0], F::zero());
+    assert_ne!(evals[2], F::zero());
+}
+
+#[test]
+fn varbase_mul_padding_test() {
+    assert!(VarbaseMul::<F>::vanishes_on_padding());
+
+    // the combined constraints also vanish on padding rows of a larger domain
+    let domain = D::<F>::new(8).unwrap();
+    let padding: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); domain.size()]);
+    let alpha = F::from(7u32);
+    let evals = VarbaseMul::evaluate_over_domain(&padding, domain, alpha);
+    assert!(evals.iter().all(|e| e.is_zero()));
+}
+
+#[test]
+fn varbase_mul_residuals_test() {
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+    let bits = [true, false, true, true, false];
+
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 2]);
+    varbasemul::witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &bits,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+    assert!(VarbaseMul::residuals(&witness, 0).is_empty());
+
+    // an n' off by 3 is reported with the residual 3, and nothing else
+    witness[5][0] += F::from(3u32);
+    assert_eq!(
+        VarbaseMul::residuals(&witness, 0),
+        vec![(ConstraintTag::NRecomposition, F::from(3u32))]
+    );
+    let gate = &CircuitGate::<F>::create_vbmul(&[Wire::new(0), Wire::new(1)])[0];
+    assert_eq!(
+        gate.verify_vbmul_residuals(0, &witness),
+        Err((
+            VarbaseMulError::NRecomposition,
+            vec![(ConstraintTag::NRecomposition, F::from(3u32))]
+        ))
+    );
+    witness[5][0] -= F::from(3u32);
+
+    // a non-boolean bit breaks its booleanity, with residual b^2 - b, and the equations using it
+    witness[2][1] = F::from(2u32);
+    let residuals = VarbaseMul::residuals(&witness, 0);
+    assert!(residuals.contains(&(ConstraintTag::Boolean(0), F::from(2u32))));
+    assert!(residuals
+        .iter()
+        .any(|(tag, _)| *tag == ConstraintTag::S1Slope(0)));
+}
+
+#[test]
+fn varbase_mul_evaluate_residuals_test() {
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 2]);
+    varbasemul::witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &[true, false, true, true, false],
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+    let residuals = VarbaseMul::evaluate_residuals(&witness, 0);
+    assert_eq!(residuals.len(), VarbaseMul::<F>::CONSTRAINTS as usize);
+    assert!(residuals.iter().all(|r| r.is_zero()));
+
+    // n' only appears in its recomposition, which is off by exactly the nudge
+    witness[5][0] += F::from(3u64);
+    let residuals = VarbaseMul::evaluate_residuals(&witness, 0);
+    let nonzero: Vec<_> = residuals
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| !r.is_zero())
+        .collect();
+    assert_eq!(nonzero.len(), 1);
+    let (i, r) = nonzero[0];
+    assert_eq!(i, 0);
+    assert!(*r == F::from(3u64) || *r == -F::from(3u64));
+    assert_eq!(
+        VarbaseMul::residuals(&witness, 0),
+        vec![(ConstraintTag::NRecomposition, *r)]
+    );
+}
+
+#[test]
+fn varbase_mul_verify_test() {
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+    let bits = [true, false, true, true, false];
+
+    let gate = CircuitGate::<F> {
+        typ: GateType::VarBaseMul,
+        wires: Wire::new(0),
+        coeffs: vec![],
+    };
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 2]);
+    varbasemul::witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &bits,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+    assert_eq!(gate.verify_vbmul(0, &witness), Ok(()));
+
+    // flipping b1 keeps it boolean, but n' no longer recomposes from the bits
+    witness[3][1] = F::one();
+    assert_eq!(
+        gate.verify_vbmul(0, &witness),
+        Err(VarbaseMulError::NRecomposition)
+    );
+    witness[3][1] = F::zero();
+
+    // nudging s1 of the second bit only breaks its slope
+    witness[8][1] += F::one();
+    assert_eq!(
+        gate.verify_vbmul(0, &witness),
+        Err(VarbaseMulError::SlopeConstraint { index: 1 })
+    );
+    witness[8][1] -= F::one();
+
+    // the intermediate accumulator is the output of the first bit
+    witness[7][0] += F::one();
+    assert_eq!(
+        gate.verify_vbmul(0, &witness),
+        Err(VarbaseMulError::OutputX { index: 0 })
+    );
+    witness[7][0] -= F::one();
+
+    // the y coordinate of the last accumulator is only the output of the last bit
+    witness[1][1] += F::one();
+    assert_eq!(
+        gate.verify_vbmul(0, &witness),
+        Err(VarbaseMulError::OutputY { index: 4 })
+    );
+    witness[1][1] -= F::one();
+
+    // b0 = 3 is caught by its booleanity once n' is recomposed from it
+    witness[5][0] += F::from(32u32);
+    witness[2][1] = F::from(3u32);
+    assert_eq!(
+        gate.verify_vbmul(0, &witness),
+        Err(VarbaseMulError::BooleanBit { bit: 0 })
+    );
+
+    // the errors still read as messages at the call sites expecting a String
+    assert_eq!(
+        String::from(VarbaseMulError::BooleanBit { bit: 0 }),
+        "boolean constraint of bit 0 failed"
+    );
+}
+
+#[test]
+fn varbase_mul_witness_with_chunking_test() {
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+    let bits = [
+        true, false, true, true, false, false, true, true, false, true,
+    ];
+
+    // chunks of 5 bits lay the same witness as the default chunking
+    let mut expected: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 4]);
+    let res = varbasemul::witness(
+        &mut expected,
+        0,
+        (base.x, base.y),
+        &bits,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 4]);
+    let chunked = varbasemul::witness_with_chunking(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &bits,
+        (acc.x, acc.y),
+        5,
+    )
+    .unwrap();
+    assert_eq!(witness, expected);
+    assert_eq!(chunked.acc, res.acc);
+    assert_eq!(chunked.n, res.n);
+
+    assert_eq!(
+        varbasemul::witness_with_chunking(
+            &mut witness,
+            0,
+            (base.x, base.y),
+            &bits[..7],
+            (acc.x, acc.y),
+            5
+        )
+        .err(),
+        Some(varbasemul::WitnessError::ChunkSize { bits: 7, chunk: 5 })
+    );
+    for chunk in [0, 6] {
+        assert_eq!(
+            varbasemul::witness_with_chunking(
+                &mut witness,
+                0,
+                (base.x, base.y),
+                &bits,
+                (acc.x, acc.y),
+                chunk
+            )
+            .err(),
+            Some(varbasemul::WitnessError::InvalidChunk { chunk, slots: 5 })
+        );
+    }
+}
+
+#[test]
+fn varbase_mul_padding_bits_test() {
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+    let bits = [true, false, true, true, false, false, true];
+
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 4]);
+    let res = varbasemul::witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &bits,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+    assert_eq!(res.pad_bits, 3);
+    assert_eq!(res.n, F::from(0b1011001u32));
+
+    // the pad bits only move the start of the double-and-add over the bits of the scalar
+    let start = reference_acc((base.x, base.y), &[false; 3], (acc.x, acc.y));
+    assert_eq!(res.acc, reference_acc((base.x, base.y), &bits, start));
+    for row in [0, 2] {
+        assert!(VarbaseMul::residuals(&witness, row).is_empty());
+    }
+
+    // no padding is needed for whole chunks
+    let res = varbasemul::witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &bits[..5],
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+    assert_eq!(res.pad_bits, 0);
+}
+
+#[test]
+fn varbase_mul_witness_affine_test() {
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+    let bits = [
+        true, false, true, true, false, false, true, true, false, true,
+    ];
+
+    let mut expected: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 4]);
+    let res = varbasemul::witness(
+        &mut expected,
+        0,
+        (base.x, base.y),
+        &bits,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+
+    // the points of Pallas, whose base field is the field of the circuit, are passed as they are
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 4]);
+    let affine = varbasemul::witness_affine(&mut witness, 0, base, &bits, acc);
+    assert_eq!(witness, expected);
+    assert_eq!(affine.acc, res.acc);
+    assert_eq!(affine.n, res.n);
+}
+
+#[test]
+fn varbase_mul_curve_params_test() {
+    let pallas = CurveParams::of::<PallasParameters>();
+    assert_eq!(pallas.a, F::zero());
+    assert_eq!(pallas.b, F::from(5u64));
+
+    let base = Other::prime_subgroup_generator();
+    assert!(pallas.is_on_curve((base.x, base.y)));
+    assert!(!pallas.is_on_curve((base.x, base.y + F::one())));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "must lie on the same curve")]
+fn varbase_mul_witness_acc0_off_curve_test() {
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 2]);
+    varbasemul::witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &[true, false, true, true, false],
+        (acc.x, acc.y + F::one()),
+        &CurveParams::of::<PallasParameters>(),
+    );
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "cannot be its base")]
+fn varbase_mul_witness_acc0_is_base_test() {
+    let base = Other::prime_subgroup_generator();
+
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 2]);
+    varbasemul::witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &[true, false, true, true, false],
+        (base.x, base.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+}
+
+#[test]
+fn varbase_mul_bit_order_test() {
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+    let rng = &mut StdRng::from_seed([5; 32]);
+    let bits_lsb: Vec<bool> = (0..20).map(|_| bool::rand(rng)).collect();
+    let bits_msb: Vec<bool> = bits_lsb.iter().rev().copied().collect();
+
+    let mut msb: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 8]);
+    let res_msb = varbasemul::witness_with_order(
+        &mut msb,
+        0,
+        (base.x, base.y),
+        &bits_msb,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+        varbasemul::BitOrder::MsbFirst,
+    );
+    let mut lsb: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 8]);
+    let res_lsb = varbasemul::witness_with_order(
+        &mut lsb,
+        0,
+        (base.x, base.y),
+        &bits_lsb,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+        varbasemul::BitOrder::LsbFirst,
+    );
+    assert_eq!(lsb, msb);
+    assert_eq!(res_lsb.acc, res_msb.acc);
+    assert_eq!(res_lsb.n, res_msb.n);
+
+    let n = bits_lsb
+        .iter()
+        .rev()
+        .fold(F::zero(), |n, b| n.double() + F::from(*b as u64));
+    assert_eq!(res_lsb.n, n);
+    assert_eq!(
+        res_lsb.acc,
+        reference_acc((base.x, base.y), &bits_msb, (acc.x, acc.y))
+    );
+}
+
+#[test]
+fn varbase_mul_witness_precomputed_test() {
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+    let rng = &mut StdRng::from_seed([6; 32]);
+    let bits_msb: Vec<bool> = (0..255).map(|_| bool::rand(rng)).collect();
+    let rows = 2 * 51;
+
+    let start = Instant::now();
+    let mut expected: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); rows]);
+    let res = varbasemul::witness(
+        &mut expected,
+        0,
+        (base.x, base.y),
+        &bits_msb,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+    println!("{}{:?}", "Witness time: ".yellow(), start.elapsed());
+
+    let start = Instant::now();
+    let cache = varbasemul::BaseCache::new((base.x, base.y));
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); rows]);
+    let precomputed = varbasemul::witness_precomputed(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &cache,
+        &bits_msb,
+        (acc.x, acc.y),
+    );
+    println!(
+        "{}{:?}",
+        "Precomputed witness time: ".yellow(),
+        start.elapsed()
+    );
+
+    assert_eq!(cache.neg_y, -cache.pos_y);
+    assert_eq!(witness, expected);
+    assert_eq!(precomputed.acc, res.acc);
+    assert_eq!(precomputed.n, res.n);
+}
+
+#[test]
+fn varbase_mul_n_bits_test() {
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+    let x = F::from(0xbeefu32);
+    let bits_lsb: Vec<bool> = BitIteratorLE::new(x.into_repr()).take(20).collect();
+    let bits_msb: Vec<bool> = bits_lsb.iter().rev().copied().collect();
+
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 8]);
+    let res = varbasemul::witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &bits_msb,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+    assert_eq!(res.n, x);
+    assert_eq!(res.n_bits(20), bits_lsb);
+    // 0xbeef has 16 bits
+    assert_eq!(res.n_bits(16), bits_lsb[..16].to_vec());
+    assert_eq!(res.n_bits(300).len(), 300);
+    assert!(res.n_bits(300)[16..].iter().all(|b| !b));
+}
+
+#[test]
+fn varbase_mul_witness_with_trace_test() {
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+    let bits = [
+        true, false, true, true, false, false, true, true, false, true,
+    ];
+
+    let mut expected: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 4]);
+    varbasemul::witness(
+        &mut expected,
+        0,
+        (base.x, base.y),
+        &bits,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 4]);
+    let (res, trace) =
+        varbasemul::witness_with_trace(&mut witness, 0, (base.x, base.y), &bits, (acc.x, acc.y));
+    assert_eq!(witness, expected);
+    assert_eq!(trace.len(), 2);
+    assert_eq!(trace[0][0], (acc.x, acc.y));
+    assert_eq!(trace[1][0], trace[0][5]);
+    assert_eq!(trace[1][5], res.acc);
+
+    // every bit computes Acc := Acc + (Q + Acc), with Q = base if the bit is set and -base otherwise
+    let point = |(x, y): (F, F)| Other::new(x, y, false).into_projective();
+    for (accs, bs) in trace.iter().zip(bits.chunks(5)) {
+        for (i, b) in bs.iter().enumerate() {
+            let q = if *b { g } else { -g };
+            let expected = point(accs[i]) + (q + point(accs[i]));
+            assert_eq!(point(accs[i + 1]), expected);
+        }
+    }
+}
+
+#[test]
+fn varbase_mul_exceptional_addition_test() {
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 4]);
+
+    // an accumulator equal to the base has no slope with it
+    let res = varbasemul::try_witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &[true; 5],
+        (base.x, base.y),
+        None,
+    );
+    assert_eq!(
+        res.err(),
+        Some(varbasemul::WitnessError::ExceptionalAddition { chunk: 0, bit: 0 })
+    );
+
+    // Acc + (Q + Acc) divides by zero when Q + Acc = -Acc, that is Acc = base / 2 for a zero bit.
+    // Each zero bit takes Acc to 2 * Acc - base, so starting from 63/64 * base reaches base / 2
+    // at the first bit of the second chunk
+    type Scalar = <Other as AffineCurve>::ScalarField;
+    let start = Scalar::from(63u32) / Scalar::from(64u32);
+    let start = g.mul(start.into_repr()).into_affine();
+    let res = varbasemul::try_witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &[false; 10],
+        (start.x, start.y),
+        None,
+    );
+    assert_eq!(
+        res.err(),
+        Some(varbasemul::WitnessError::ExceptionalAddition { chunk: 1, bit: 0 })
+    );
+}
+
+#[test]
+fn varbase_mul_verify_chain_test() {
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+    let bits: Vec<bool> = (0..15).map(|i| i % 3 == 0).collect();
+
+    // a generic gate before the chain of 3 chunks, and one after
+    let mut gates = vec![CircuitGate::<F>::zero(Wire::new(0))];
+    gates.extend(CircuitGate::create_vbmul_chain(1, 3));
+    gates.push(CircuitGate::zero(Wire::new(7)));
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 8]);
+    varbasemul::witness(
+        &mut witness,
+        1,
+        (base.x, base.y),
+        &bits,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+    assert_eq!(varbasemul::verify_vbmul_chain(&gates, &witness, 1), Ok(()));
+    assert_eq!(
+        varbasemul::verify_vbmul_chain(&gates, &witness, 0),
+        Err((0, VarbaseMulError::NotVarBaseMul))
+    );
+
+    // the third chunk starts from a wrong n, which its own rows cannot tell
+    witness[4][5] += F::one();
+    witness[5][5] += F::from(32u32);
+    assert_eq!(gates[5].verify_vbmul(5, &witness), Ok(()));
+    assert_eq!(
+        varbasemul::verify_vbmul_chain(&gates, &witness, 1),
+        Err((5, VarbaseMulError::NCarry))
+    );
+    witness[4][5] -= F::one();
+    witness[5][5] -= F::from(32u32);
+
+    // a broken slope in the second chunk
+    witness[7][4] += F::one();
+    assert_eq!(
+        varbasemul::verify_vbmul_chain(&gates, &witness, 1),
+        Err((3, VarbaseMulError::SlopeConstraint { index: 0 }))
+    );
+}
+
+#[test]
+fn varbase_mul_hamming_weight_test() {
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+
+    // 0b1011_0000_0111_0001 has weight 7
+    let scalar = 0b1011_0000_0111_0001u32;
+    let bits: Vec<_> = (0..20).rev().map(|i| (scalar >> i) & 1 == 1).collect();
+
+    // the chain starts at row 2 of a larger witness
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 12]);
+    varbasemul::witness(
+        &mut witness,
+        2,
+        (base.x, base.y),
+        &bits,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+
+    assert_eq!(varbasemul::hamming_weight(&witness, 2, 8), 7);
+    // the first chunk holds 0b00001
+    assert_eq!(varbasemul::hamming_weight(&witness, 2, 2), 1);
+    assert_eq!(varbasemul::hamming_weight(&witness, 0, 2), 0);
+}
+
+#[test]
+fn varbase_mul_assert_scalar_eq_test() {
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+
+    // the cofactor-like scalar 0b10_0000_1001
+    let bits: Vec<_> = (0..10)
+        .rev()
+        .map(|i| (0b10_0000_1001u32 >> i) & 1 == 1)
+        .collect();
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 4]);
+    varbasemul::witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &bits,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+
+    assert_eq!(
+        varbasemul::assert_scalar_eq(&witness, 0, 4, F::from(0b10_0000_1001u32)),
+        Ok(())
+    );
+    assert!(varbasemul::assert_scalar_eq(&witness, 0, 4, F::from(0b10_0000_1000u32)).is_err());
+    // the first chunk alone multiplies by its 5 most significant bits
+    assert_eq!(
+        varbasemul::assert_scalar_eq(&witness, 0, 2, F::from(0b10000u32)),
+        Ok(())
+    );
+    assert!(varbasemul::assert_scalar_eq(&witness, 0, 3, F::from(0b10000u32)).is_err());
+}
+
+#[test]
+fn varbase_mul_canonical_bits_test() {
+    // 0b101 = 5
+    let bits = [true, false, true];
+    assert_eq!(
+        varbasemul::canonical_bits(&bits, 5),
+        Ok(vec![false, false, true, false, true])
+    );
+    assert_eq!(varbasemul::canonical_bits(&bits, 3), Ok(bits.to_vec()));
+    // leading zeros are dropped
+    assert_eq!(
+        varbasemul::canonical_bits(&[false, false, true, false, true], 3),
+        Ok(bits.to_vec())
+    );
+    // set bits are not
+    assert!(varbasemul::canonical_bits(&bits, 2).is_err());
+}
+
+#[test]
+fn varbase_mul_constraints_touching_test() {
+    let annotated = VarbaseMul::<F>::constraints_annotated();
+    let tags = |col| -> HashSet<_> {
+        VarbaseMul::<F>::constraints_touching(col)
+            .into_iter()
+            .map(|i| annotated[i].0)
+            .collect()
+    };
+
+    // column 9 holds x2 on the current row, the output of the second bit and input of the third,
+    // and s2 on the next row, the slope of the third bit
+    let expected: HashSet<_> = [
+        ConstraintTag::OutputX(1),
+        ConstraintTag::OutputY(1),
+        ConstraintTag::S1Slope(2),
+        ConstraintTag::OutputX(2),
+        ConstraintTag::OutputY(2),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(tags(9), expected);
+
+    // column 4 holds n on the current row and the bit b2 on the next one
+    let expected: HashSet<_> = [
+        ConstraintTag::NRecomposition,
+        ConstraintTag::Boolean(2),
+        ConstraintTag::S1Slope(2),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(tags(4), expected);
+
+    // the indices are sorted and within bounds
+    let touching = VarbaseMul::<F>::constraints_touching(0);
+    assert!(touching.windows(2).all(|w| w[0] < w[1]));
+    assert!(touching
+        .iter()
+        .all(|i| *i < VarbaseMul::<F>::CONSTRAINTS as usize));
+    assert!(VarbaseMul::<F>::constraints_touching(COLUMNS).is_empty());
+}
+
+proptest! {
+    #[test]
+    fn varbase_mul_montgomery_ladder_test(seed: [u8; 32], chunks in 1..5usize) {
+        let rng = &mut StdRng::from_seed(seed);
+        let bits: Vec<_> = (0..5 * chunks).map(|_| bool::rand(rng)).collect();
+
+        let base = Other::prime_subgroup_generator()
+            .mul(<Other as AffineCurve>::ScalarField::rand(rng).into_repr());
+        let acc = (base + base).into_affine();
+        let base = base.into_affine();
+
+        let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 2 * chunks]);
+        let res = varbasemul::witness(&mut witness, 0, (base.x, base.y), &bits, (acc.x, acc.y), &CurveParams::of::<PallasParameters>());
+
+        let expected = varbasemul::montgomery_ladder_scalar_mul((base.x, base.y), &bits, (acc.x, acc.y));
+        prop_assert_eq!(expected, Some(res.acc));
+    }
+}
+
+proptest! {
+    #[test]
+    fn varbase_mul_check_n_recomposition_test(n_prev: u64, chunk in 0..32u64) {
+        let bits = [4, 3, 2, 1, 0].map(|i| F::from((chunk >> i) & 1));
+        let n_prev = F::from(n_prev);
+        let n_next = F::from(32u64) * n_prev + F::from(chunk);
+        prop_assert!(varbasemul::check_n_recomposition(n_prev, &bits, n_next));
+        prop_assert!(!varbasemul::check_n_recomposition(n_prev, &bits, n_next + F::one()));
+        prop_assert!(!varbasemul::check_n_recomposition(n_prev, &bits, n_next - F::one()));
+        prop_assert!(!varbasemul::check_n_recomposition(n_prev + F::one(), &bits, n_next));
+    }
+}
+
+#[test]
+fn varbase_mul_witness_reference_test() {
+    type ScalarField = <Other as AffineCurve>::ScalarField;
+    let num_bits = 50;
+    let rng = &mut StdRng::from_seed([7; 32]);
+
+    let mut scalars: Vec<u64> = (0..20).map(|_| rng.gen_range(0..1 << num_bits)).collect();
+    scalars.extend([0, 1, (1 << num_bits) - 1]);
+
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+    for x in scalars {
+        let bits: Vec<_> = (0..num_bits).rev().map(|i| (x >> i) & 1 == 1).collect();
+        let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 2 * num_bits / 5]);
+        let res = varbasemul::witness(
+            &mut witness,
+            0,
+            (base.x, base.y),
+            &bits,
+            (acc.x, acc.y),
+            &CurveParams::of::<PallasParameters>(),
+        );
+
+        // starting from [2]T, the chain computes [2^n + 2x + 1]T
+        let scalar = ScalarField::from(1u64 << num_bits)
+            + ScalarField::from(x).double()
+            + ScalarField::one();
+        let expected = g.mul(scalar.into_repr()).into_affine();
+        assert_eq!(res.acc, (expected.x, expected.y), "scalar {}", x);
+        assert_eq!(res.n, F::from(x));
+    }
+}
+
+#[test]
+fn varbase_mul_bit_test() {
+    assert_eq!(Bit::try_from(F::zero()), Ok(Bit::zero()));
+    assert_eq!(Bit::try_from(F::one()), Ok(Bit::one()));
+    assert_eq!(
+        Bit::try_from(F::from(2u64)),
+        Err(varbasemul::WitnessError::NonBooleanBit)
+    );
+    assert_eq!(
+        Bit::try_from(-F::one()),
+        Err(varbasemul::WitnessError::NonBooleanBit)
+    );
+    assert_eq!(Bit::<F>::from(true).value(), F::one());
+    assert_eq!(Bit::<F>::from(false).value(), F::zero());
+}
+
+#[test]
+fn varbase_mul_select_y_test() {
+    let base = Other::prime_subgroup_generator();
+    let cache = varbasemul::BaseCache::new((base.x, base.y));
+    assert_eq!(varbasemul::neg_base_y((base.x, base.y)), -base.y);
+    assert_eq!(cache.select_y(Bit::zero()), -base.y);
+    assert_eq!(cache.select_y(Bit::one()), base.y);
+
+    // the selection agrees with the (2b - 1) * yt form of the constraint
+    for b in [Bit::zero(), Bit::one()] {
+        assert_eq!(cache.select_y(b), (b.value().double() - F::one()) * base.y);
+    }
+}
+
+#[test]
+fn varbase_mul_chunk_ns_test() {
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+    let rng = &mut StdRng::from_seed([8; 32]);
+    let bits: Vec<bool> = (0..20).map(|_| bool::rand(rng)).collect();
+
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 8]);
+    let res = varbasemul::witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &bits,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+    assert_eq!(res.chunk_ns.len(), 4);
+    assert_eq!(res.chunk_ns[0].0, F::zero());
+    for pair in res.chunk_ns.windows(2) {
+        assert_eq!(pair[0].1, pair[1].0);
+    }
+    assert_eq!(res.chunk_ns.last().unwrap().1, res.n);
+    // the pairs are the n and n' cells of each chunk
+    for (i, (n_prev, n_next)) in res.chunk_ns.iter().enumerate() {
+        assert_eq!(witness[4][2 * i], *n_prev);
+        assert_eq!(witness[5][2 * i], *n_next);
+    }
+}
+
+#[test]
+fn varbase_mul_read_bits_test() {
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+    let bits = [
+        true, false, true, true, false, false, false, true, false, true, true, true, false, false,
+        true,
+    ];
+
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 8]);
+    varbasemul::witness(
+        &mut witness,
+        2,
+        (base.x, base.y),
+        &bits,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+    assert_eq!(varbasemul::read_bits(&witness, 2, 3), Ok(bits.to_vec()));
+
+    // the padding zeros are read back too
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 4]);
+    varbasemul::witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &bits[..7],
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+    let mut padded = vec![false; 3];
+    padded.extend_from_slice(&bits[..7]);
+    assert_eq!(varbasemul::read_bits(&witness, 0, 2), Ok(padded));
+
+    witness[4][1] = F::from(2u64);
+    assert_eq!(
+        varbasemul::read_bits(&witness, 0, 2),
+        Err(varbasemul::WitnessError::NonBooleanBit)
+    );
+}
+
+#[test]
+fn varbase_mul_zero_companion_test() {
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+    let bits = [true, false, true, true, false];
+
+    // stale values left on the companion row outside of the layout are cleared
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::one(); 2]);
+    varbasemul::witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &bits,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+    assert_eq!(varbasemul::verify_zero_companion(&witness, 0), Ok(()));
+    for col in 12..COLUMNS {
+        assert_eq!(witness[col][1], F::zero());
+    }
+
+    // the companion of the chunk is laid one row too far from its VarBaseMul row
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 3]);
+    varbasemul::try_witness_on_rows(
+        &mut witness,
+        [(0, 2)].into_iter(),
+        (base.x, base.y),
+        &bits,
+        (acc.x, acc.y),
+        None,
+    )
+    .unwrap();
+    assert!(varbasemul::verify_zero_companion(&witness, 0).is_err());
+
+    // each Zero gate of a chain is wired to the row its VarBaseMul gate reads as Next
+    let gates = CircuitGate::<F>::create_vbmul_chain(0, 2);
+    for i in 0..2 {
+        let next_row = 2 * i + CurrOrNext::Next.shift();
+        assert_eq!(gates[2 * i + 1].typ, GateType::Zero);
+        assert_eq!(gates[2 * i + 1].wires, Wire::new(next_row));
+    }
+
+    // swapping the companion rows of two chunks breaks both
+    let mut witness: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 4]);
+    varbasemul::witness(
+        &mut witness,
+        0,
+        (base.x, base.y),
+        &[bits, bits].concat(),
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+    for column in witness.iter_mut() {
+        column.swap(1, 3);
+    }
+    assert!(varbasemul::verify_zero_companion(&witness, 0).is_err());
+    assert!(varbasemul::verify_zero_companion(&witness, 2).is_err());
+}
+
+#[test]
+fn varbase_mul_witness_on_rows_test() {
+    let rng = &mut StdRng::from_seed([5; 32]);
+    let bits: Vec<_> = (0..15).map(|_| bool::rand(rng)).collect();
+
+    let base = Other::prime_subgroup_generator();
+    let g = base.into_projective();
+    let acc = (g + g).into_affine();
+
+    let mut contiguous: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 6]);
+    let expected = varbasemul::witness(
+        &mut contiguous,
+        0,
+        (base.x, base.y),
+        &bits,
+        (acc.x, acc.y),
+        &CurveParams::of::<PallasParameters>(),
+    );
+
+    // the chunks are placed in descending order of rows
+    let mut descending: [Vec<F>; COLUMNS] = array_init(|_| vec![F::zero(); 6]);
+    let rows = [(4, 5), (2, 3), (0, 1)];
+    let res = varbasemul::try_witness_on_rows(
+        &mut descending,
+        rows.into_iter(),
+        (base.x, base.y),
+        &bits,
+        (acc.x, acc.y),
+        None,
+    )
+    .unwrap();
+    assert_eq!(res.acc, expected.acc);
+    assert_eq!(res.n, expected.n);
+    for (chunk, (row, _)) in rows.iter().enumerate() {
+        assert!(VarbaseMul::residuals(&descending, *row).is_empty());
+        for (d, c) in descending.iter().zip(&contiguous) {
+            assert_eq!(d[*row], c[2 * chunk]);
+            assert_eq!(d[*row + 1], c[2 * chunk + 1]);
+        }
+    }
+
+    // there must be a pair of rows for every chunk
+    assert_eq!(
+        varbasemul::try_witness_on_rows(
+            &mut descending,
+            rows[..2].iter().copied(),
+            (base.x, base.y),
+            &bits,
+            (acc.x, acc.y),
+            None,
+        )
+        .err(),
+        Some(varbasemul::WitnessError::MissingRows { chunks: 3 })
+    );
+}
+
+#[test]
+fn varbase_mul_correction_constraint_test() {
+    let rng = &mut StdRng::from_seed([6; 32]);
+    let p = Other::prime_subgroup_generator();
+    let q = p.mul(<Other as AffineCurve>::ScalarField::rand(rng).into_repr());
+    let diff = (q - p.into_projective()).into_affine();
+    let q = q.into_affine();
+
+    let constants = Constants {
+        alpha: F::zero(),
+        beta: F::zero(),
+        gamma: F::zero(),
+        joint_combiner: F::zero(),
+        endo_coefficient: F::zero(),
+        mds: vec![],
+    };
+    let domain = D::<F>::new(2).unwrap();
+    let evaluate = |row: [F; 8]| -> Vec<F> {
+        let mut curr = [F::zero(); COLUMNS];
+        curr[..8].copy_from_slice(&row);
+        let evals = [
+            ProofEvaluations::dummy_with_witness_evaluations(curr),
+            ProofEvaluations::dummy_with_witness_evaluations([F::zero(); COLUMNS]),
+        ];
+        VarbaseMul::<F>::correction_constraint()
+            .iter()
+            .map(|c| {
+                c.evaluate_(domain, domain.group_gen, &evals, &constants)
+                    .unwrap()
+            })
+            .collect()
+    };
+    let vanishes = |row| evaluate(row).iter().all(|c| c.is_zero());
+
+    // d_0 = 0 subtracts P
+    let s = (q.y + p.y) / (q.x - p.x);
+    assert!(vanishes([p.x, p.y, q.x, q.y, F::zero(), diff.x, diff.y, s]));
+    assert!(!vanishes([p.x, p.y, q.x, q.y, F::zero(), q.x, q.y, s]));
+
+    // d_0 = 1 keeps Q, whatever the slope
+    assert!(vanishes([
+        p.x,
+        p.y,
+        q.x,
+        q.y,
+        F::one(),
+        q.x,
+        q.y,
+        F::zero()
+    ]));
+    assert!(!vanishes([p.x, p.y, q.x, q.y, F::one(), diff.x, diff.y, s]));
+
+    // d_0 must be a bit
+    assert!(!vanishes([
+        p.x,
+        p.y,
+        q.x,
+        q.y,
+        F::from(2u32),
+        q.x,
+        q.y,
+        F::zero()
+    ]));
+}
+
+#[test]
+fn varbase_mul_constraints_count_test() {
+    assert_eq!(
+        VarbaseMul::<F>::constraints().len() as u32,
+        VarbaseMul::<F>::CONSTRAINTS
+    );
+    assert_eq!(VarbaseMul::<F>::CONSTRAINTS, 1 + 4 * 5);
+}
+
+/// Collects the identifiers of the cached subexpressions of a constraint
+fn collect_caches(e: &E<F>, ids: &mut HashSet<CacheId>) {
+    match e {
+        Expr::Constant(_)
+        | Expr::Cell(_)
+        | Expr::VanishesOnLast4Rows
+        | Expr::UnnormalizedLagrangeBasis(_) => (),
+        Expr::Double(x) | Expr::Square(x) | Expr::Pow(x, _) => collect_caches(x, ids),
+        Expr::BinOp(_, x, y) => {
+            collect_caches(x, ids);
+            collect_caches(y, ids);
+        }
+        Expr::Cache(id, x) => {
+            ids.insert(*id);
+            collect_caches(x, ids);
+        }
+    }
+}
+
+#[test]
+fn varbase_mul_cache_count_test() {
+    let mut ids = HashSet::new();
+    for c in VarbaseMul::<F>::constraints() {
+        collect_caches(&c, &mut ids);
+    }
+    // the denominator t and the numerator u of the second slope of each of the 5 bits,
+    // where the square of the first slope is only read through t
+    assert_eq!(ids.len(), 2 * 5, "{} cached subexpressions", ids.len());
+}
+
+#[test]
+fn varbase_mul_count_mul_gates_test() {
+    // for each bit: 1 to constrain it, 2 for its slope, 1 for s1^2 in t, 1 for u,
+    // 3 for the output x and 2 for the output y, while n' only takes doublings
+    assert_eq!(count_mul_gates(&VarbaseMul::<F>::constraints()), 5 * 10);
+}
+
+#[test]
+fn varbase_mul_gate_footprint_test() {
+    let footprint = VarbaseMul::<F>::gate_footprint();
+    assert_eq!(
+        footprint,
+        GateFootprint {
+            rows: 2,
+            constraints: VarbaseMul::<F>::CONSTRAINTS,
+            next_row_used: true,
+        }
+    );
+    assert_eq!(footprint.constraints, 21);
+    // the layout spans the same rows as the constraints read
+    assert_eq!(
+        footprint,
+        GateFootprint {
+            rows: 2,
+            constraints: 21,
+            next_row_used: VarbaseMul::<F>::constraints()
+                .iter()
+                .any(|c| c.references_row(CurrOrNext::Next)),
+        }
+    );
+    // gates reading only their own row take a single one
+    let footprint_add = CompleteAdd::<F>::gate_footprint();
+    assert_eq!(
+        (footprint_add.rows, footprint_add.next_row_used),
+        (1, false)
+    );
+    assert_eq!(EndosclMul::<F>::gate_footprint().rows, 2);
+    // a chain of chunks takes as many rows as its gates
+    assert_eq!(
+        CircuitGate::<F>::create_vbmul_chain(0, 3).len(),
+        3 * footprint.rows
+    );
+}
+
+#[test]
+fn varbase_mul_mul_count_test() {
+    assert_eq!(VarbaseMul::<F>::mul_count(5), 65);
+    assert_eq!(VarbaseMul::<F>::mul_count(255), 3315);
+    // a chunk of 5 bits costs 65 operations, whatever its position in the chain
+    assert_eq!(
+        VarbaseMul::<F>::mul_count(255),
+        51 * VarbaseMul::<F>::mul_count(5)
+    );
+    // the pad bits complete the last chunk
+    assert_eq!(VarbaseMul::<F>::mul_count(3), 65);
+    assert_eq!(VarbaseMul::<F>::mul_count(254), 3315);
+    assert_eq!(VarbaseMul::<F>::mul_count(256), 3380);
+}
+
+/// Writes a constraint in a stable textual form, with the literals in hexadecimal
+/// and the cached subexpressions inlined
+fn serialize_expr(e: &E<F>) -> String {
+    match e {
+        Expr::Constant(ConstantExpr::Literal(x)) => x.to_hex(),
+        Expr::Constant(c) => format!("{:?}", c),
+        Expr::Cell(v) => format!("cell({:?}, {:?})", v.col, v.row),
+        Expr::Double(x) => format!("double({})", serialize_expr(x)),
+        Expr::Square(x) => format!("square({})", serialize_expr(x)),
+        Expr::Pow(x, n) => format!("pow({}, {})", serialize_expr(x), n),
+        Expr::BinOp(op, x, y) => {
+            let op = match op {
+                Op2::Add => "+",
+                Op2::Mul => "*",
+                Op2::Sub => "-",
+            };
+            format!("({} {} {})", serialize_expr(x), op, serialize_expr(y))
+        }
+        Expr::VanishesOnLast4Rows => "vanishes_on_last_4_rows".to_string(),
+        Expr::UnnormalizedLagrangeBasis(i) => format!("lagrange({})", i),
+        Expr::Cache(id, x) => format!("cache({:?}, {})", id, serialize_expr(x)),
+    }
+}
+
+#[test]
+fn varbase_mul_constraints_golden_test() {
+    let lines: Vec<String> = VarbaseMul::<F>::constraints_annotated()
+        .iter()
+        .map(|(tag, c)| format!("{:?}: {}", tag, serialize_expr(c)))
+        .collect();
+
+    // regenerate with KIMCHI_UPDATE_GOLDEN=1 after an intended change to the constraints
+    if std::env::var("KIMCHI_UPDATE_GOLDEN").is_ok() {
+        let path = format!(
+            "{}/src/tests/test_vectors/varbasemul_constraints.txt",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        std::fs::write(path, lines.join("\n") + "\n").unwrap();
+        return;
+    }
+
+    let golden: Vec<&str> = include_str!("test_vectors/varbasemul_constraints.txt")
+        .lines()
+        .collect();
+    assert_eq!(lines.len(), golden.len(), "number of constraints changed");
+    for (line, expected) in lines.iter().zip(golden) {
+        assert_eq!(
+            line, expected,
+            "constraint differs from the golden file, set KIMCHI_UPDATE_GOLDEN=1 if intended"
+        );
+    }
+}